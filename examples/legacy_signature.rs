@@ -0,0 +1,32 @@
+//! Measure the verification speed of legacy signatures, which hash the whole
+//! input with SHA-512
+//!
+//! Run with `cargo run --release --example legacy_signature`, and add
+//! `--features sha512-x86` to use the SHA512 instructions when available.
+
+use std::time::{Duration, Instant};
+
+use minisign_verify::{PublicKey, Signature};
+
+fn main() {
+    let public_key =
+        PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+            .expect("Unable to decode the public key");
+    let signature = Signature::decode(
+        "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==",
+    )
+    .expect("Unable to decode the signature");
+    let input = vec![0x5a; 16 << 20];
+
+    // Keep the best of a few runs, to filter out noise
+    let mut best = Duration::MAX;
+    for _ in 0..5 {
+        let start = Instant::now();
+        assert!(public_key.verify(&input, &signature, true).is_err());
+        best = best.min(start.elapsed());
+    }
+    println!("{:.0} MiB/s", 16.0 / best.as_secs_f64());
+}
@@ -0,0 +1,44 @@
+//! Compare the verification speed with and without a prepared public key
+//!
+//! Run with `cargo run --release --example prepared_public_key`
+
+use std::time::Instant;
+
+use minisign_verify::{PreparedPublicKey, PublicKey, Signature};
+
+const ITERATIONS: u32 = 10_000;
+
+fn main() {
+    let public_key =
+        PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+            .expect("Unable to decode the public key");
+    let signature = Signature::decode(
+        "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==",
+    )
+    .expect("Unable to decode the signature");
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        public_key.verify(b"test", &signature, false).unwrap();
+    }
+    let unprepared = start.elapsed();
+
+    let prepared_public_key = PreparedPublicKey::new(public_key);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        prepared_public_key
+            .verify(b"test", &signature, false)
+            .unwrap();
+    }
+    let prepared = start.elapsed();
+
+    println!(
+        "PublicKey: {:?}/verification, PreparedPublicKey: {:?}/verification ({:.2}x)",
+        unprepared / ITERATIONS,
+        prepared / ITERATIONS,
+        unprepared.as_secs_f64() / prepared.as_secs_f64()
+    );
+}
@@ -0,0 +1,32 @@
+//! Measure the throughput of `StreamVerifier::update()`
+//!
+//! Run with `cargo run --release --example stream_verifier`
+
+use std::time::Instant;
+
+use minisign_verify::{PublicKey, Signature};
+
+const ITERATIONS: u32 = 256;
+
+fn main() {
+    let public_key =
+        PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+            .expect("Unable to decode the public key");
+    let signature = Signature::decode(
+        "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==",
+    )
+    .expect("Unable to decode the signature");
+    let chunk = vec![0x5a; 1 << 20];
+
+    let mut verifier = public_key.verify_stream(&signature).unwrap();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        verifier.update(&chunk);
+    }
+    let elapsed = start.elapsed();
+    assert!(verifier.finalize().is_err());
+    println!("{:.0} MiB/s", ITERATIONS as f64 / elapsed.as_secs_f64());
+}
@@ -0,0 +1,80 @@
+#ifndef MINISIGN_VERIFY_H
+#define MINISIGN_VERIFY_H
+
+/*
+ * C bindings for the minisign-verify crate.
+ *
+ * Handles are created by the *_decode(), *_from_file() and *_new()
+ * functions, and must be released with the matching *_free() function.
+ *
+ * Fallible functions return MINISIGN_OK (0) on success, or one of the
+ * MINISIGN_ERR_* codes. These values are stable.
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define MINISIGN_OK 0
+#define MINISIGN_ERR_INVALID_ENCODING 1
+#define MINISIGN_ERR_INVALID_TRUSTED_COMMENT 2
+#define MINISIGN_ERR_INVALID_SIGNATURE 3
+#define MINISIGN_ERR_INSUFFICIENT_SIGNATURES 4
+#define MINISIGN_ERR_INVALID_PASSWORD 5
+#define MINISIGN_ERR_CORRUPTED_SECRET_KEY 6
+#define MINISIGN_ERR_CLOCK_UNAVAILABLE 7
+#define MINISIGN_ERR_SIGNATURE_EXPIRED 8
+#define MINISIGN_ERR_SIGNATURE_FROM_FUTURE 9
+#define MINISIGN_ERR_IO 10
+#define MINISIGN_ERR_UNEXPECTED_ALGORITHM 11
+#define MINISIGN_ERR_UNEXPECTED_FILE_NAME 12
+#define MINISIGN_ERR_UNEXPECTED_KEY_ID 13
+#define MINISIGN_ERR_INPUT_TOO_LARGE 14
+#define MINISIGN_ERR_INVALID_STREAM_STATE 15
+#define MINISIGN_ERR_UNSUPPORTED_ALGORITHM 16
+#define MINISIGN_ERR_UNSUPPORTED_LEGACY_MODE 17
+#define MINISIGN_ERR_INVALID_ARGUMENT 100
+
+typedef struct minisign_public_key minisign_public_key;
+typedef struct minisign_signature minisign_signature;
+typedef struct minisign_stream_verifier minisign_stream_verifier;
+
+/* Return a static, NUL-terminated description of an error code */
+const char *minisign_error_message(int code);
+
+/* Public keys */
+int minisign_public_key_from_base64(const char *base64, minisign_public_key **out);
+int minisign_public_key_decode(const char *lines, minisign_public_key **out);
+int minisign_public_key_from_file(const char *path, minisign_public_key **out);
+void minisign_public_key_free(minisign_public_key *public_key);
+
+/* Signatures */
+int minisign_signature_decode(const char *lines, minisign_signature **out);
+int minisign_signature_from_file(const char *path, minisign_signature **out);
+/* The returned string is not NUL-terminated; its length is stored into *len */
+const char *minisign_signature_trusted_comment(const minisign_signature *signature, size_t *len);
+void minisign_signature_free(minisign_signature *signature);
+
+/* One-shot verification */
+int minisign_verify(const minisign_public_key *public_key, const uint8_t *data, size_t len,
+                    const minisign_signature *signature, int allow_legacy);
+int minisign_verify_file(const minisign_public_key *public_key, const char *path,
+                         const minisign_signature *signature, int allow_legacy);
+
+/* Streaming verification (pre-hashed signatures only) */
+int minisign_stream_verifier_new(const minisign_public_key *public_key,
+                                 const minisign_signature *signature,
+                                 minisign_stream_verifier **out);
+int minisign_stream_verifier_update(minisign_stream_verifier *stream_verifier,
+                                    const uint8_t *data, size_t len);
+int minisign_stream_verifier_finalize(minisign_stream_verifier *stream_verifier);
+void minisign_stream_verifier_free(minisign_stream_verifier *stream_verifier);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,431 @@
+//! C bindings for [minisign-verify](https://crates.io/crates/minisign-verify).
+//!
+//! Public keys, signatures and stream verifiers are exposed as opaque
+//! handles, created by `*_decode()`/`*_from_file()`/`*_new()` functions and
+//! released with the matching `*_free()` function.
+//!
+//! Every fallible function returns `MINISIGN_OK` (`0`) on success, or one of
+//! the `MINISIGN_ERR_*` codes. These values are part of the ABI and will
+//! never change.
+//!
+//! The C declarations are in `include/minisign_verify.h`.
+
+#![allow(non_camel_case_types)]
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::{ptr, slice};
+
+use minisign_verify::{
+    Error, PublicKey, Signature, StreamVerifier, VerificationPolicy, DEFAULT_LEGACY_SIZE_LIMIT,
+};
+
+/// Success
+pub const MINISIGN_OK: c_int = 0;
+/// The provided string couldn't be decoded properly
+pub const MINISIGN_ERR_INVALID_ENCODING: c_int = 1;
+/// A field of the trusted comment is malformed, duplicated, or missing
+pub const MINISIGN_ERR_INVALID_TRUSTED_COMMENT: c_int = 2;
+/// The signature verification failed
+pub const MINISIGN_ERR_INVALID_SIGNATURE: c_int = 3;
+/// Fewer distinct keys than required produced a valid signature
+pub const MINISIGN_ERR_INSUFFICIENT_SIGNATURES: c_int = 4;
+/// The password of an encrypted secret key is incorrect
+pub const MINISIGN_ERR_INVALID_PASSWORD: c_int = 5;
+/// The secret key is damaged
+pub const MINISIGN_ERR_CORRUPTED_SECRET_KEY: c_int = 6;
+/// No clock is available to check the timestamp
+pub const MINISIGN_ERR_CLOCK_UNAVAILABLE: c_int = 7;
+/// The signature has expired
+pub const MINISIGN_ERR_SIGNATURE_EXPIRED: c_int = 8;
+/// The signature timestamp is in the future
+pub const MINISIGN_ERR_SIGNATURE_FROM_FUTURE: c_int = 9;
+/// An I/O error occurred
+pub const MINISIGN_ERR_IO: c_int = 10;
+/// The algorithm doesn't match what was expected
+pub const MINISIGN_ERR_UNEXPECTED_ALGORITHM: c_int = 11;
+/// The file name in the trusted comment doesn't match the expected one
+pub const MINISIGN_ERR_UNEXPECTED_FILE_NAME: c_int = 12;
+/// The key ID from the signature doesn't match the public key
+pub const MINISIGN_ERR_UNEXPECTED_KEY_ID: c_int = 13;
+/// The input is larger than the configured limit
+pub const MINISIGN_ERR_INPUT_TOO_LARGE: c_int = 14;
+/// A saved stream verification state is invalid
+pub const MINISIGN_ERR_INVALID_STREAM_STATE: c_int = 15;
+/// The signature algorithm is not supported
+pub const MINISIGN_ERR_UNSUPPORTED_ALGORITHM: c_int = 16;
+/// Legacy signatures are not supported by stream verifiers
+pub const MINISIGN_ERR_UNSUPPORTED_LEGACY_MODE: c_int = 17;
+/// A required pointer is `NULL`, or a string is not valid UTF-8
+pub const MINISIGN_ERR_INVALID_ARGUMENT: c_int = 100;
+
+fn status(result: Result<(), Error>) -> c_int {
+    match result {
+        Ok(()) => MINISIGN_OK,
+        Err(e) => e.code(),
+    }
+}
+
+/// An opaque public key
+pub struct minisign_public_key(PublicKey);
+
+/// An opaque signature
+pub struct minisign_signature(Signature);
+
+/// An opaque stream verifier
+pub struct minisign_stream_verifier(StreamVerifier<'static>);
+
+unsafe fn to_str<'a>(s: *const c_char) -> Result<&'a str, c_int> {
+    if s.is_null() {
+        return Err(MINISIGN_ERR_INVALID_ARGUMENT);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| MINISIGN_ERR_INVALID_ARGUMENT)
+}
+
+unsafe fn to_slice<'a>(data: *const u8, len: usize) -> Result<&'a [u8], c_int> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(MINISIGN_ERR_INVALID_ARGUMENT);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn new_handle<T, U>(
+    s: *const c_char,
+    out: *mut *mut U,
+    decode: fn(&str) -> Result<T, Error>,
+    wrap: fn(T) -> U,
+) -> c_int {
+    if out.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    *out = ptr::null_mut();
+    let s = match to_str(s) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    match decode(s) {
+        Ok(x) => {
+            *out = Box::into_raw(Box::new(wrap(x)));
+            MINISIGN_OK
+        }
+        Err(e) => e.code(),
+    }
+}
+
+/// Return a static, NUL-terminated description of an error code
+#[no_mangle]
+pub extern "C" fn minisign_error_message(code: c_int) -> *const c_char {
+    let message: &'static [u8] = match code {
+        MINISIGN_OK => b"Success\0",
+        MINISIGN_ERR_INVALID_ENCODING => b"Invalid encoding in minisign data\0",
+        MINISIGN_ERR_INVALID_TRUSTED_COMMENT => b"Invalid trusted comment\0",
+        MINISIGN_ERR_INVALID_SIGNATURE => b"The signature verification failed\0",
+        MINISIGN_ERR_INSUFFICIENT_SIGNATURES => b"Not enough valid signatures\0",
+        MINISIGN_ERR_INVALID_PASSWORD => b"Wrong password for the secret key\0",
+        MINISIGN_ERR_CORRUPTED_SECRET_KEY => b"The secret key is corrupted\0",
+        MINISIGN_ERR_CLOCK_UNAVAILABLE => b"No clock is available to check the timestamp\0",
+        MINISIGN_ERR_SIGNATURE_EXPIRED => b"The signature has expired\0",
+        MINISIGN_ERR_SIGNATURE_FROM_FUTURE => b"The signature timestamp is in the future\0",
+        MINISIGN_ERR_IO => b"I/O error\0",
+        MINISIGN_ERR_UNEXPECTED_ALGORITHM => b"Unexpected signature algorithm\0",
+        MINISIGN_ERR_UNEXPECTED_FILE_NAME => {
+            b"The signature was created for a different file than the one provided\0"
+        }
+        MINISIGN_ERR_UNEXPECTED_KEY_ID => {
+            b"The signature was created with a different key than the one provided\0"
+        }
+        MINISIGN_ERR_INPUT_TOO_LARGE => b"The input exceeds the size limit\0",
+        MINISIGN_ERR_INVALID_STREAM_STATE => b"Invalid or corrupted stream state\0",
+        MINISIGN_ERR_UNSUPPORTED_ALGORITHM => {
+            b"This signature algorithm is not supported by this implementation\0"
+        }
+        MINISIGN_ERR_UNSUPPORTED_LEGACY_MODE => {
+            b"Stream verifiers only support non-legacy mode signatures\0"
+        }
+        MINISIGN_ERR_INVALID_ARGUMENT => b"Invalid argument\0",
+        _ => b"Unknown error\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+/// Create a public key from a base64 string
+///
+/// # Safety
+///
+/// `base64` must be a NUL-terminated string, and `out` a valid pointer. On
+/// success, `*out` must later be released with `minisign_public_key_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_public_key_from_base64(
+    base64: *const c_char,
+    out: *mut *mut minisign_public_key,
+) -> c_int {
+    new_handle(base64, out, PublicKey::from_base64, minisign_public_key)
+}
+
+/// Create a public key from a string, as in the `minisign.pub` file
+///
+/// # Safety
+///
+/// Same as `minisign_public_key_from_base64()`.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_public_key_decode(
+    lines: *const c_char,
+    out: *mut *mut minisign_public_key,
+) -> c_int {
+    new_handle(lines, out, PublicKey::decode, minisign_public_key)
+}
+
+/// Load a public key from a file
+///
+/// # Safety
+///
+/// Same as `minisign_public_key_from_base64()`.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_public_key_from_file(
+    path: *const c_char,
+    out: *mut *mut minisign_public_key,
+) -> c_int {
+    new_handle(
+        path,
+        out,
+        |path| PublicKey::from_file(path),
+        minisign_public_key,
+    )
+}
+
+/// Release a public key
+///
+/// # Safety
+///
+/// `public_key` must be `NULL` or a handle that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_public_key_free(public_key: *mut minisign_public_key) {
+    if !public_key.is_null() {
+        drop(Box::from_raw(public_key));
+    }
+}
+
+/// Create a signature from a string, as in a `.sig` file
+///
+/// # Safety
+///
+/// `lines` must be a NUL-terminated string, and `out` a valid pointer. On
+/// success, `*out` must later be released with `minisign_signature_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_signature_decode(
+    lines: *const c_char,
+    out: *mut *mut minisign_signature,
+) -> c_int {
+    new_handle(lines, out, Signature::decode, minisign_signature)
+}
+
+/// Load a signature from a `.sig` file
+///
+/// # Safety
+///
+/// Same as `minisign_signature_decode()`.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_signature_from_file(
+    path: *const c_char,
+    out: *mut *mut minisign_signature,
+) -> c_int {
+    new_handle(
+        path,
+        out,
+        |path| Signature::from_file(path),
+        minisign_signature,
+    )
+}
+
+/// Return the trusted comment of a signature, without the
+/// `trusted comment: ` prefix
+///
+/// The returned string is not NUL-terminated: its length is stored into
+/// `*len`. It remains valid until the signature is released.
+///
+/// # Safety
+///
+/// `signature` must be a valid handle, and `len` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_signature_trusted_comment(
+    signature: *const minisign_signature,
+    len: *mut usize,
+) -> *const c_char {
+    if signature.is_null() || len.is_null() {
+        return ptr::null();
+    }
+    let trusted_comment = (*signature).0.trusted_comment();
+    *len = trusted_comment.len();
+    trusted_comment.as_ptr() as *const c_char
+}
+
+/// Release a signature
+///
+/// # Safety
+///
+/// `signature` must be `NULL` or a handle that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_signature_free(signature: *mut minisign_signature) {
+    if !signature.is_null() {
+        drop(Box::from_raw(signature));
+    }
+}
+
+/// Verify that `signature` is a valid signature for `data`
+///
+/// Legacy signatures are only accepted if `allow_legacy` is not `0`.
+///
+/// # Safety
+///
+/// `public_key` and `signature` must be valid handles, and `data` must point
+/// to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_verify(
+    public_key: *const minisign_public_key,
+    data: *const u8,
+    len: usize,
+    signature: *const minisign_signature,
+    allow_legacy: c_int,
+) -> c_int {
+    if public_key.is_null() || signature.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    let data = match to_slice(data, len) {
+        Ok(data) => data,
+        Err(code) => return code,
+    };
+    status(
+        (*public_key)
+            .0
+            .verify(data, &(*signature).0, allow_legacy != 0),
+    )
+}
+
+/// Verify that `signature` is a valid signature for the file at `path`
+///
+/// Legacy signatures are only accepted if `allow_legacy` is not `0`. Files
+/// with a legacy signature are loaded in memory, and must not be larger than
+/// 64 MiB.
+///
+/// # Safety
+///
+/// `public_key` and `signature` must be valid handles, and `path` a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_verify_file(
+    public_key: *const minisign_public_key,
+    path: *const c_char,
+    signature: *const minisign_signature,
+    allow_legacy: c_int,
+) -> c_int {
+    if public_key.is_null() || signature.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    let path = match to_str(path) {
+        Ok(path) => path,
+        Err(code) => return code,
+    };
+    let legacy_size_limit = if allow_legacy != 0 {
+        DEFAULT_LEGACY_SIZE_LIMIT
+    } else {
+        0
+    };
+    status(
+        (*public_key)
+            .0
+            .verify_file_with_limit(path, &(*signature).0, legacy_size_limit)
+            .map(|_| ()),
+    )
+}
+
+/// Create a stream verifier
+///
+/// The verifier keeps its own copies of the public key and the signature,
+/// so their handles can be released at any time.
+///
+/// # Safety
+///
+/// `public_key` and `signature` must be valid handles, and `out` a valid
+/// pointer. On success, `*out` must later be released with
+/// `minisign_stream_verifier_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_stream_verifier_new(
+    public_key: *const minisign_public_key,
+    signature: *const minisign_signature,
+    out: *mut *mut minisign_stream_verifier,
+) -> c_int {
+    if public_key.is_null() || signature.is_null() || out.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    *out = ptr::null_mut();
+    match (*public_key)
+        .0
+        .clone()
+        .into_stream_verifier((*signature).0.clone(), VerificationPolicy::new())
+    {
+        Ok(stream_verifier) => {
+            *out = Box::into_raw(Box::new(minisign_stream_verifier(stream_verifier)));
+            MINISIGN_OK
+        }
+        Err(e) => e.code(),
+    }
+}
+
+/// Update a stream verifier with a chunk of data
+///
+/// # Safety
+///
+/// `stream_verifier` must be a valid handle, and `data` must point to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_stream_verifier_update(
+    stream_verifier: *mut minisign_stream_verifier,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    if stream_verifier.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    match to_slice(data, len) {
+        Ok(data) => {
+            (*stream_verifier).0.update(data);
+            MINISIGN_OK
+        }
+        Err(code) => code,
+    }
+}
+
+/// Verify the signature over the data processed so far
+///
+/// # Safety
+///
+/// `stream_verifier` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_stream_verifier_finalize(
+    stream_verifier: *mut minisign_stream_verifier,
+) -> c_int {
+    if stream_verifier.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    status((*stream_verifier).0.finalize())
+}
+
+/// Release a stream verifier
+///
+/// # Safety
+///
+/// `stream_verifier` must be `NULL` or a handle that hasn't been released
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_stream_verifier_free(
+    stream_verifier: *mut minisign_stream_verifier,
+) {
+    if !stream_verifier.is_null() {
+        drop(Box::from_raw(stream_verifier));
+    }
+}
@@ -0,0 +1,291 @@
+use std::ffi::{CStr, CString};
+use std::{fs, ptr, slice};
+
+use minisign_verify_ffi::*;
+
+const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+
+const PREHASHED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==";
+
+const LEGACY_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==";
+
+fn public_key() -> *mut minisign_public_key {
+    let base64 = CString::new(PUBLIC_KEY).unwrap();
+    let mut public_key = ptr::null_mut();
+    assert_eq!(
+        unsafe { minisign_public_key_from_base64(base64.as_ptr(), &mut public_key) },
+        MINISIGN_OK
+    );
+    public_key
+}
+
+fn signature(lines: &str) -> *mut minisign_signature {
+    let lines = CString::new(lines).unwrap();
+    let mut signature = ptr::null_mut();
+    assert_eq!(
+        unsafe { minisign_signature_decode(lines.as_ptr(), &mut signature) },
+        MINISIGN_OK
+    );
+    signature
+}
+
+#[test]
+fn verify() {
+    let public_key = public_key();
+    let prehashed = signature(PREHASHED_SIGNATURE);
+    let legacy = signature(LEGACY_SIGNATURE);
+    unsafe {
+        assert_eq!(
+            minisign_verify(public_key, b"test".as_ptr(), 4, prehashed, 0),
+            MINISIGN_OK
+        );
+        assert_eq!(
+            minisign_verify(public_key, b"Test".as_ptr(), 4, prehashed, 0),
+            MINISIGN_ERR_INVALID_SIGNATURE
+        );
+        assert_eq!(
+            minisign_verify(public_key, b"test".as_ptr(), 4, legacy, 0),
+            MINISIGN_ERR_UNEXPECTED_ALGORITHM
+        );
+        assert_eq!(
+            minisign_verify(public_key, b"test".as_ptr(), 4, legacy, 1),
+            MINISIGN_OK
+        );
+
+        let mut len = 0;
+        let trusted_comment = minisign_signature_trusted_comment(prehashed, &mut len);
+        assert_eq!(
+            slice::from_raw_parts(trusted_comment as *const u8, len),
+            b"timestamp:1633700835\tfile:test\tprehashed"
+        );
+
+        minisign_signature_free(legacy);
+        minisign_signature_free(prehashed);
+        minisign_public_key_free(public_key);
+    }
+}
+
+#[test]
+fn stream_verifier() {
+    let public_key = public_key();
+    let prehashed = signature(PREHASHED_SIGNATURE);
+    let legacy = signature(LEGACY_SIGNATURE);
+    unsafe {
+        let mut stream_verifier = ptr::null_mut();
+        assert_eq!(
+            minisign_stream_verifier_new(public_key, prehashed, &mut stream_verifier),
+            MINISIGN_OK
+        );
+        // The verifier doesn't borrow the key and the signature
+        minisign_signature_free(prehashed);
+        minisign_public_key_free(public_key);
+        for chunk in [&b"te"[..], &b""[..], &b"st"[..]] {
+            assert_eq!(
+                minisign_stream_verifier_update(stream_verifier, chunk.as_ptr(), chunk.len()),
+                MINISIGN_OK
+            );
+        }
+        assert_eq!(
+            minisign_stream_verifier_finalize(stream_verifier),
+            MINISIGN_OK
+        );
+        minisign_stream_verifier_free(stream_verifier);
+
+        let public_key = self::public_key();
+        let mut stream_verifier = ptr::null_mut();
+        assert_eq!(
+            minisign_stream_verifier_new(public_key, legacy, &mut stream_verifier),
+            MINISIGN_ERR_UNSUPPORTED_LEGACY_MODE
+        );
+        assert!(stream_verifier.is_null());
+        minisign_signature_free(legacy);
+        minisign_public_key_free(public_key);
+    }
+}
+
+#[test]
+fn files() {
+    let dir = std::env::temp_dir().join(format!("minisign-verify-ffi-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("test"), b"test").unwrap();
+    fs::write(dir.join("test.sig"), PREHASHED_SIGNATURE).unwrap();
+    fs::write(
+        dir.join("minisign.pub"),
+        format!("untrusted comment: minisign public key\n{}\n", PUBLIC_KEY),
+    )
+    .unwrap();
+    let path = |name: &str| CString::new(dir.join(name).to_str().unwrap()).unwrap();
+    unsafe {
+        let mut public_key = ptr::null_mut();
+        assert_eq!(
+            minisign_public_key_from_file(path("minisign.pub").as_ptr(), &mut public_key),
+            MINISIGN_OK
+        );
+        let mut signature = ptr::null_mut();
+        assert_eq!(
+            minisign_signature_from_file(path("test.sig").as_ptr(), &mut signature),
+            MINISIGN_OK
+        );
+        assert_eq!(
+            minisign_verify_file(public_key, path("test").as_ptr(), signature, 0),
+            MINISIGN_OK
+        );
+        assert_eq!(
+            minisign_verify_file(public_key, path("missing").as_ptr(), signature, 0),
+            MINISIGN_ERR_IO
+        );
+        minisign_signature_free(signature);
+
+        let legacy = self::signature(LEGACY_SIGNATURE);
+        assert_eq!(
+            minisign_verify_file(public_key, path("test").as_ptr(), legacy, 0),
+            MINISIGN_ERR_UNEXPECTED_ALGORITHM
+        );
+        assert_eq!(
+            minisign_verify_file(public_key, path("test").as_ptr(), legacy, 1),
+            MINISIGN_OK
+        );
+        minisign_signature_free(legacy);
+        minisign_public_key_free(public_key);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn invalid_arguments() {
+    unsafe {
+        let mut public_key = ptr::null_mut();
+        assert_eq!(
+            minisign_public_key_from_base64(ptr::null(), &mut public_key),
+            MINISIGN_ERR_INVALID_ARGUMENT
+        );
+        let invalid = CString::new("not a key").unwrap();
+        assert_eq!(
+            minisign_public_key_from_base64(invalid.as_ptr(), &mut public_key),
+            MINISIGN_ERR_INVALID_ENCODING
+        );
+        assert!(public_key.is_null());
+        assert_eq!(
+            minisign_verify(ptr::null(), ptr::null(), 0, ptr::null(), 0),
+            MINISIGN_ERR_INVALID_ARGUMENT
+        );
+        minisign_public_key_free(ptr::null_mut());
+        minisign_signature_free(ptr::null_mut());
+        minisign_stream_verifier_free(ptr::null_mut());
+
+        let message = CStr::from_ptr(minisign_error_message(MINISIGN_ERR_INVALID_SIGNATURE));
+        assert_eq!(
+            message.to_str().unwrap(),
+            "The signature verification failed"
+        );
+        let message = CStr::from_ptr(minisign_error_message(-1));
+        assert_eq!(message.to_str().unwrap(), "Unknown error");
+    }
+}
+
+/// The checked-in header must declare every exported function, with the same
+/// error code values as the Rust constants
+#[test]
+fn header_matches_exports() {
+    let header = include_str!("../include/minisign_verify.h");
+    let source = include_str!("../src/lib.rs");
+    let mut exported = 0;
+    for line in source.lines() {
+        let line = line.trim_start();
+        let rest = match line
+            .strip_prefix("pub unsafe extern \"C\" fn ")
+            .or_else(|| line.strip_prefix("pub extern \"C\" fn "))
+        {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let name = &rest[..rest.find('(').unwrap()];
+        assert!(
+            header.contains(&format!(" {}(", name)) || header.contains(&format!("*{}(", name)),
+            "{} is not declared in the header",
+            name
+        );
+        exported += 1;
+    }
+    assert_eq!(exported, header.matches(");").count());
+
+    let mut constants = 0;
+    for line in source.lines() {
+        let rest = match line.strip_prefix("pub const ") {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let name = &rest[..rest.find(':').unwrap()];
+        let value = rest[rest.find('=').unwrap() + 1..]
+            .trim()
+            .trim_end_matches(';');
+        assert!(
+            header.contains(&format!("#define {} {}\n", name, value)),
+            "{} doesn't match the header",
+            name
+        );
+        constants += 1;
+    }
+    assert_eq!(
+        constants,
+        header.matches("#define MINISIGN_ERR_").count() + 1
+    );
+}
+
+#[test]
+fn error_codes() {
+    use minisign_verify::Error;
+
+    let errors = [
+        (Error::InvalidEncoding, MINISIGN_ERR_INVALID_ENCODING),
+        (
+            Error::InvalidTrustedComment("file"),
+            MINISIGN_ERR_INVALID_TRUSTED_COMMENT,
+        ),
+        (Error::InvalidSignature, MINISIGN_ERR_INVALID_SIGNATURE),
+        (
+            Error::InsufficientSignatures {
+                valid: 1,
+                required: 2,
+            },
+            MINISIGN_ERR_INSUFFICIENT_SIGNATURES,
+        ),
+        (Error::InvalidPassword, MINISIGN_ERR_INVALID_PASSWORD),
+        (Error::CorruptedSecretKey, MINISIGN_ERR_CORRUPTED_SECRET_KEY),
+        (Error::ClockUnavailable, MINISIGN_ERR_CLOCK_UNAVAILABLE),
+        (Error::SignatureExpired, MINISIGN_ERR_SIGNATURE_EXPIRED),
+        (
+            Error::SignatureFromFuture,
+            MINISIGN_ERR_SIGNATURE_FROM_FUTURE,
+        ),
+        (
+            Error::IoError(std::io::ErrorKind::NotFound.into()),
+            MINISIGN_ERR_IO,
+        ),
+        (
+            Error::UnexpectedAlgorithm,
+            MINISIGN_ERR_UNEXPECTED_ALGORITHM,
+        ),
+        (Error::UnexpectedFileName, MINISIGN_ERR_UNEXPECTED_FILE_NAME),
+        (Error::UnexpectedKeyId, MINISIGN_ERR_UNEXPECTED_KEY_ID),
+        (Error::InputTooLarge, MINISIGN_ERR_INPUT_TOO_LARGE),
+        (Error::InvalidStreamState, MINISIGN_ERR_INVALID_STREAM_STATE),
+        (
+            Error::UnsupportedAlgorithm,
+            MINISIGN_ERR_UNSUPPORTED_ALGORITHM,
+        ),
+        (
+            Error::UnsupportedLegacyMode,
+            MINISIGN_ERR_UNSUPPORTED_LEGACY_MODE,
+        ),
+    ];
+    for (error, code) in errors.iter() {
+        assert_eq!(error.code(), *code);
+    }
+}
@@ -0,0 +1,112 @@
+use std::io::{self, Read, Write};
+
+use crate::{Error, StreamVerifier};
+
+/// A reader that verifies a signature over the data read through it
+///
+/// Every byte read from the inner reader is fed into a `StreamVerifier`, so
+/// that the content can be consumed (for example, unpacked) and verified in
+/// a single pass.
+///
+/// The data must not be trusted until `finish()` returns successfully.
+///
+/// ```rust,no_run
+/// use minisign_verify::{PublicKey, Signature, VerifyingReader};
+/// use std::fs::File;
+/// use std::io::{self, Read};
+///
+/// let public_key = PublicKey::from_file("minisign.pub").expect("Unable to load the public key");
+/// let signature = Signature::from_file("archive.tar.sig").expect("Unable to load the signature");
+/// let stream_verifier = public_key
+///     .verify_stream(&signature)
+///     .expect("Unable to create stream verifier");
+///
+/// let file = File::open("archive.tar").expect("Unable to open file");
+/// let mut reader = VerifyingReader::new(file, stream_verifier);
+/// io::copy(&mut reader, &mut io::sink()).expect("Error reading file");
+/// reader.finish().expect("Signature verification failed");
+/// ```
+pub struct VerifyingReader<'a, R> {
+    inner: R,
+    stream_verifier: StreamVerifier<'a>,
+}
+
+impl<'a, R: Read> VerifyingReader<'a, R> {
+    /// Wrap `inner`, feeding everything read from it into `stream_verifier`
+    pub fn new(inner: R, stream_verifier: StreamVerifier<'a>) -> Self {
+        VerifyingReader {
+            inner,
+            stream_verifier,
+        }
+    }
+
+    /// Return a reference to the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Verify the signature over the data read so far
+    ///
+    /// Data that hasn't been read from the inner reader is not verified.
+    /// Returns the inner reader if the signature is valid.
+    pub fn finish(mut self) -> Result<R, Error> {
+        self.stream_verifier.finalize()?;
+        Ok(self.inner)
+    }
+}
+
+impl<R: Read> Read for VerifyingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.stream_verifier.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// A writer that verifies a signature over the data written through it
+///
+/// Every byte accepted by the inner writer is fed into a `StreamVerifier`.
+///
+/// The written data must not be trusted until `finish()` returns
+/// successfully.
+pub struct VerifyingWriter<'a, W> {
+    inner: W,
+    stream_verifier: StreamVerifier<'a>,
+}
+
+impl<'a, W: Write> VerifyingWriter<'a, W> {
+    /// Wrap `inner`, feeding everything written to it into `stream_verifier`
+    pub fn new(inner: W, stream_verifier: StreamVerifier<'a>) -> Self {
+        VerifyingWriter {
+            inner,
+            stream_verifier,
+        }
+    }
+
+    /// Return a reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Flush the inner writer and verify the signature over the data
+    /// written so far
+    ///
+    /// Returns the inner writer if the signature is valid.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.inner.flush()?;
+        self.stream_verifier.finalize()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for VerifyingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.stream_verifier.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,5 +1,8 @@
 #![forbid(unsafe_code)]
 
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use core::fmt::{self, Display};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -10,6 +13,7 @@ pub enum Error {
     InvalidInput,
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
 impl Display for Error {
@@ -36,6 +40,24 @@ pub trait Decoder {
     }
 }
 
+pub trait Encoder {
+    /// Length of `bin_len` bytes after encoding.
+    fn encoded_len(bin_len: usize) -> Result<usize, Error>;
+
+    /// Encode `bin` into `encoded`.
+    /// The output buffer can be larger than required; the returned slice is
+    /// a view of the buffer with the correct length.
+    fn encode<IN: AsRef<[u8]>>(encoded: &mut [u8], bin: IN) -> Result<&[u8], Error>;
+
+    /// Encode `bin` into a `String`.
+    fn encode_to_string<IN: AsRef<[u8]>>(bin: IN) -> Result<String, Error> {
+        let mut encoded = vec![0u8; Self::encoded_len(bin.as_ref().len())?];
+        let encoded_len = Self::encode(&mut encoded, bin)?.len();
+        encoded.truncate(encoded_len);
+        Ok(String::from_utf8(encoded).unwrap())
+    }
+}
+
 struct Base64Impl;
 
 impl Base64Impl {
@@ -74,6 +96,48 @@ impl Base64Impl {
         x | (Self::_eq(x, 0) & (Self::_eq(c, b'A') ^ 0xff))
     }
 
+    #[inline]
+    fn b64_byte_to_char(x: u8) -> u8 {
+        (Self::_lt(x, 26) & (x.wrapping_add(b'A')))
+            | (Self::_ge(x, 26) & Self::_lt(x, 52) & (x.wrapping_add(b'a'.wrapping_sub(26))))
+            | (Self::_ge(x, 52) & Self::_lt(x, 62) & (x.wrapping_add(b'0'.wrapping_sub(52))))
+            | (Self::_eq(x, 62) & b'+')
+            | (Self::_eq(x, 63) & b'/')
+    }
+
+    pub fn encoded_len(bin_len: usize) -> Result<usize, Error> {
+        let nibbles = bin_len.checked_add(2).ok_or(Error::Overflow)? / 3;
+        nibbles.checked_mul(4).ok_or(Error::Overflow)
+    }
+
+    pub fn encode<'t>(b64: &'t mut [u8], bin: &[u8]) -> Result<&'t [u8], Error> {
+        let b64_len = Self::encoded_len(bin.len())?;
+        if b64.len() < b64_len {
+            return Err(Error::Overflow);
+        }
+        let mut acc = 0u16;
+        let mut acc_len = 0usize;
+        let mut b64_pos = 0usize;
+        for &v in bin {
+            acc = (acc << 8) + v as u16;
+            acc_len += 8;
+            while acc_len >= 6 {
+                acc_len -= 6;
+                b64[b64_pos] = Self::b64_byte_to_char(((acc >> acc_len) & 0x3f) as u8);
+                b64_pos += 1;
+            }
+        }
+        if acc_len > 0 {
+            b64[b64_pos] = Self::b64_byte_to_char(((acc << (6 - acc_len)) & 0x3f) as u8);
+            b64_pos += 1;
+        }
+        while b64_pos < b64_len {
+            b64[b64_pos] = b'=';
+            b64_pos += 1;
+        }
+        Ok(&b64[..b64_pos])
+    }
+
     fn skip_padding(b64: &[u8], mut padding_len: usize) -> Result<&[u8], Error> {
         let b64_len = b64.len();
         let mut b64_pos = 0usize;
@@ -133,6 +197,18 @@ impl Base64Impl {
 
 pub struct Base64;
 
+impl Encoder for Base64 {
+    #[inline]
+    fn encoded_len(bin_len: usize) -> Result<usize, Error> {
+        Base64Impl::encoded_len(bin_len)
+    }
+
+    #[inline]
+    fn encode<IN: AsRef<[u8]>>(b64: &mut [u8], bin: IN) -> Result<&[u8], Error> {
+        Base64Impl::encode(b64, bin.as_ref())
+    }
+}
+
 impl Decoder for Base64 {
     #[inline]
     fn decode<IN: AsRef<[u8]>>(bin: &mut [u8], b64: IN) -> Result<&[u8], Error> {
@@ -158,3 +234,19 @@ fn test_base64_invalid_padding() {
         Err(Error::InvalidInput)
     );
 }
+
+#[test]
+fn test_base64_encode() {
+    let bin: Vec<u8> = (0u8..=255).collect();
+    for len in 0..bin.len() {
+        let b64 = Base64::encode_to_string(&bin[..len]).unwrap();
+        assert_eq!(b64.len(), Base64::encoded_len(len).unwrap());
+        assert_eq!(Base64::decode_to_vec(&b64).unwrap(), &bin[..len]);
+    }
+    assert_eq!(Base64::encode_to_string(b"f").unwrap(), "Zg==");
+    assert_eq!(Base64::encode_to_string(b"fo").unwrap(), "Zm8=");
+    assert_eq!(Base64::encode_to_string(b"foo").unwrap(), "Zm9v");
+    assert_eq!(Base64::encode_to_string(b"\xfb\xff").unwrap(), "+/8=");
+    let mut b64 = [0u8; 3];
+    assert_eq!(Base64::encode(&mut b64, b"foo"), Err(Error::Overflow));
+}
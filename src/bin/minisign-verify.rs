@@ -0,0 +1,234 @@
+//! A command-line verifier, compatible with `minisign -V`
+//!
+//! ```text
+//! minisign-verify -V [-x sigfile] [-p pubkey_file | -P pubkey] [-o] [-q | -Q] -m file
+//! ```
+//!
+//! Exits with `0` if the signature is valid, `1` if it isn't or if an error
+//! occurred, and `2` on usage errors.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+
+use minisign_verify::{Error, PublicKey, Signature};
+
+const DEFAULT_PUBLIC_KEY_FILE: &str = "minisign.pub";
+const SIGNATURE_SUFFIX: &str = ".minisig";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Verbosity {
+    Normal,
+    /// `-q`: don't print anything
+    Quiet,
+    /// `-Q`: only print the trusted comment
+    PrettyQuiet,
+}
+
+#[derive(Debug)]
+struct Options {
+    public_key_file: PathBuf,
+    public_key: Option<String>,
+    message_file: PathBuf,
+    signature_file: Option<PathBuf>,
+    output: bool,
+    verbosity: Verbosity,
+}
+
+enum Command {
+    Verify(Options),
+    Help,
+    Version,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage:
+minisign-verify -V [-x sigfile] [-p pubkey_file | -P pubkey] [-o] [-q | -Q] -m file
+
+-V                verify that a signature is valid for a given file
+-m <file>         file to verify
+-x <sigfile>      signature file (default: <file>{})
+-p <pubkey_file>  public key file (default: ./{})
+-P <pubkey>       public key, as a base64 string
+-o                output the file content after verification
+-q                quiet mode, suppress output
+-Q                pretty quiet mode, only print the trusted comment
+-h                show this help
+-v                show the version",
+        SIGNATURE_SUFFIX, DEFAULT_PUBLIC_KEY_FILE
+    );
+    process::exit(2);
+}
+
+/// Parse the command line the way `getopt()` does: short options can be
+/// grouped, and option arguments can either be attached or follow
+fn parse_args<I: IntoIterator<Item = OsString>>(args: I) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let mut public_key_file = None;
+    let mut public_key = None;
+    let mut message_file = None;
+    let mut signature_file = None;
+    let mut output = false;
+    let mut verbosity = Verbosity::Normal;
+
+    while let Some(arg) = args.next() {
+        let flags = match arg.to_str() {
+            Some("--") => break,
+            Some(arg) if arg.len() > 1 && arg.starts_with('-') => arg[1..].to_string(),
+            _ => return Err(format!("Unexpected argument: {}", arg.to_string_lossy())),
+        };
+        for (i, flag) in flags.char_indices() {
+            let slot = match flag {
+                'V' => continue,
+                'o' => {
+                    output = true;
+                    continue;
+                }
+                'q' => {
+                    verbosity = Verbosity::Quiet;
+                    continue;
+                }
+                'Q' => {
+                    verbosity = Verbosity::PrettyQuiet;
+                    continue;
+                }
+                'h' => return Ok(Command::Help),
+                'v' => return Ok(Command::Version),
+                'p' => &mut public_key_file,
+                'P' => &mut public_key,
+                'm' => &mut message_file,
+                'x' => &mut signature_file,
+                _ => return Err(format!("Unknown option: -{}", flag)),
+            };
+            let attached = &flags[i + flag.len_utf8()..];
+            *slot = if attached.is_empty() {
+                Some(
+                    args.next()
+                        .ok_or_else(|| format!("Option -{} requires an argument", flag))?,
+                )
+            } else {
+                Some(OsString::from(attached))
+            };
+            break;
+        }
+    }
+    if let Some(arg) = args.next() {
+        return Err(format!("Unexpected argument: {}", arg.to_string_lossy()));
+    }
+
+    let message_file = PathBuf::from(message_file.ok_or("A file to verify is required (-m)")?);
+    let public_key = match public_key {
+        None => None,
+        Some(public_key) => Some(
+            public_key
+                .into_string()
+                .map_err(|_| "The public key is not a valid base64 string")?,
+        ),
+    };
+    Ok(Command::Verify(Options {
+        public_key_file: public_key_file
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_PUBLIC_KEY_FILE)),
+        public_key,
+        message_file,
+        signature_file: signature_file.map(PathBuf::from),
+        output,
+        verbosity,
+    }))
+}
+
+fn load_public_key(options: &Options) -> Result<PublicKey, String> {
+    match &options.public_key {
+        Some(public_key) => PublicKey::from_base64(public_key)
+            .map_err(|e| format!("Unable to decode the public key: {}", e)),
+        None => PublicKey::from_file(&options.public_key_file)
+            .map_err(|e| format!("{}: {}", options.public_key_file.display(), e)),
+    }
+}
+
+fn verify_file(public_key: &PublicKey, options: &Options) -> Result<Signature, String> {
+    let signature_file = options.signature_file.clone().unwrap_or_else(|| {
+        let mut signature_file = options.message_file.clone().into_os_string();
+        signature_file.push(SIGNATURE_SUFFIX);
+        PathBuf::from(signature_file)
+    });
+    let signature = Signature::from_file(&signature_file)
+        .map_err(|e| format!("{}: {}", signature_file.display(), e))?;
+    // Pre-hashed signatures are verified with a `StreamVerifier`. Legacy
+    // signatures cover the content itself, which is loaded in memory with
+    // no size limit, like minisign does.
+    let res = public_key.verify_file_with_limit(&options.message_file, &signature, u64::MAX);
+    match res {
+        Ok(_) => Ok(signature),
+        Err(Error::InvalidSignature) => Err("Signature verification failed".to_string()),
+        Err(Error::UnexpectedKeyId) => Err(format!(
+            "Signature key id in {} is {}\nbut the key id in the public key is {}",
+            signature_file.display(),
+            key_id_hex(signature.key_id()),
+            key_id_hex(public_key.key_id())
+        )),
+        Err(e) => Err(format!("{}: {}", options.message_file.display(), e)),
+    }
+}
+
+/// Format a key ID like minisign does: as a little-endian 64-bit integer
+fn key_id_hex(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let public_key = load_public_key(options)?;
+    let signature = verify_file(&public_key, options)?;
+
+    // Messages go to stderr when the content is written to stdout
+    let mut info: Box<dyn Write> = if options.output {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    };
+    let res = match options.verbosity {
+        Verbosity::Normal => write!(
+            info,
+            "Signature and comment signature verified\nTrusted comment: {}\n",
+            signature.trusted_comment()
+        ),
+        Verbosity::PrettyQuiet => writeln!(info, "{}", signature.trusted_comment()),
+        Verbosity::Quiet => Ok(()),
+    };
+    res.and_then(|_| info.flush())
+        .map_err(|e| format!("Unable to write the output: {}", e))?;
+
+    if options.output {
+        let message_file = options.message_file.display();
+        let mut file =
+            File::open(&options.message_file).map_err(|e| format!("{}: {}", message_file, e))?;
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        io::copy(&mut file, &mut stdout)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| format!("{}: {}", message_file, e))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(std::env::args_os().skip(1)) {
+        Ok(Command::Verify(options)) => options,
+        Ok(Command::Help) => usage(),
+        Ok(Command::Version) => {
+            println!("minisign-verify {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n", e);
+            usage()
+        }
+    };
+    if let Err(e) = run(&options) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
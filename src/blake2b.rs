@@ -0,0 +1,443 @@
+//! BLAKE2b hashing, as specified in RFC 7693
+//!
+//! This is the implementation used to verify pre-hashed signatures. It
+//! supports keyed hashing, which can be used as a MAC, as well as salts,
+//! personalization strings and digests of 1 to 64 bytes.
+//!
+//! ```rust
+//! use minisign_verify::blake2b::{self, Params};
+//!
+//! // A 64-byte digest
+//! let digest = blake2b::hash(b"data");
+//! assert_eq!(digest.as_bytes().len(), 64);
+//!
+//! // A 32-byte MAC, computed incrementally
+//! let params = Params::new()
+//!     .digest_length(32)
+//!     .key(b"secret key")
+//!     .personal(b"MyApp cache v1");
+//! let mut state = params.to_state();
+//! state.update(b"da");
+//! state.update(b"ta");
+//! assert_eq!(state.finalize(), params.hash(b"data"));
+//! ```
+
+use core::fmt;
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::crypto::blake2b::{
+    Blake2b as State, BLAKE2B_BLOCKBYTES, BLAKE2B_KEYBYTES, BLAKE2B_OUTBYTES,
+    BLAKE2B_PERSONALBYTES, BLAKE2B_SALTBYTES,
+};
+
+/// Size of a block, in bytes
+pub const BLOCKBYTES: usize = BLAKE2B_BLOCKBYTES;
+/// Maximum size of a digest, in bytes
+pub const OUTBYTES: usize = BLAKE2B_OUTBYTES;
+/// Maximum size of a key, in bytes
+pub const KEYBYTES: usize = BLAKE2B_KEYBYTES;
+/// Maximum size of a salt, in bytes
+pub const SALTBYTES: usize = BLAKE2B_SALTBYTES;
+/// Maximum size of a personalization string, in bytes
+pub const PERSONALBYTES: usize = BLAKE2B_PERSONALBYTES;
+
+/// Hash `data` with the default parameters: no key, and a 64-byte digest
+pub fn hash(data: &[u8]) -> Digest {
+    Params::new().hash(data)
+}
+
+/// Parameters of a BLAKE2b hash function
+///
+/// Different parameters produce unrelated hash functions. The salt and the
+/// personalization string are shorter than a block and don't have to be
+/// secret: they can be used to separate the hashes computed by different
+/// applications, or for different purposes.
+#[derive(Clone)]
+pub struct Params {
+    digest_length: u8,
+    key: [u8; KEYBYTES],
+    key_length: u8,
+    salt: [u8; SALTBYTES],
+    personal: [u8; PERSONALBYTES],
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Params")
+            .field("digest_length", &self.digest_length)
+            .field("keyed", &(self.key_length > 0))
+            .field("salt", &self.salt)
+            .field("personal", &self.personal)
+            .finish()
+    }
+}
+
+impl Params {
+    /// Create parameters for unkeyed hashing, with a 64-byte digest
+    pub const fn new() -> Self {
+        Params {
+            digest_length: OUTBYTES as u8,
+            key: [0; KEYBYTES],
+            key_length: 0,
+            salt: [0; SALTBYTES],
+            personal: [0; PERSONALBYTES],
+        }
+    }
+
+    /// Set the size of the digest, in bytes
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digest_length` is `0` or larger than `OUTBYTES`.
+    pub fn digest_length(mut self, digest_length: usize) -> Self {
+        assert!(
+            digest_length > 0 && digest_length <= OUTBYTES,
+            "BLAKE2b digests must be between 1 and 64 bytes long"
+        );
+        self.digest_length = digest_length as u8;
+        self
+    }
+
+    /// Use a secret key, turning the hash function into a MAC
+    ///
+    /// An empty key is the same as no key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is larger than `KEYBYTES`.
+    pub fn key(mut self, key: &[u8]) -> Self {
+        assert!(
+            key.len() <= KEYBYTES,
+            "BLAKE2b keys must be at most 64 bytes long"
+        );
+        self.key = [0; KEYBYTES];
+        self.key[..key.len()].copy_from_slice(key);
+        self.key_length = key.len() as u8;
+        self
+    }
+
+    /// Set the salt, padded with zeros to `SALTBYTES`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `salt` is larger than `SALTBYTES`.
+    pub fn salt(mut self, salt: &[u8]) -> Self {
+        assert!(
+            salt.len() <= SALTBYTES,
+            "BLAKE2b salts must be at most 16 bytes long"
+        );
+        self.salt = [0; SALTBYTES];
+        self.salt[..salt.len()].copy_from_slice(salt);
+        self
+    }
+
+    /// Set the personalization string, padded with zeros to `PERSONALBYTES`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `personal` is larger than `PERSONALBYTES`.
+    pub fn personal(mut self, personal: &[u8]) -> Self {
+        assert!(
+            personal.len() <= PERSONALBYTES,
+            "BLAKE2b personalization strings must be at most 16 bytes long"
+        );
+        self.personal = [0; PERSONALBYTES];
+        self.personal[..personal.len()].copy_from_slice(personal);
+        self
+    }
+
+    /// Create a hasher with these parameters
+    pub fn to_state(&self) -> Blake2b {
+        Blake2b {
+            state: State::new_with_params(
+                self.digest_length as usize,
+                &self.key[..self.key_length as usize],
+                &self.salt,
+                &self.personal,
+            ),
+            digest_length: self.digest_length,
+        }
+    }
+
+    /// Hash `data` with these parameters
+    pub fn hash(&self, data: &[u8]) -> Digest {
+        let mut state = self.to_state();
+        state.update(data);
+        state.finalize()
+    }
+}
+
+/// An incremental BLAKE2b hasher
+///
+/// Data can also be written with `io::Write`, or with `core::hash::Hasher`,
+/// whose `finish()` function returns the first 8 bytes of the digest as a
+/// little-endian integer.
+#[derive(Clone)]
+pub struct Blake2b {
+    state: State,
+    digest_length: u8,
+}
+
+impl fmt::Debug for Blake2b {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The buffer may contain the key
+        f.debug_struct("Blake2b")
+            .field("digest_length", &self.digest_length)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Blake2b {
+    /// Create an unkeyed hasher, whose digests are `digest_length` bytes long
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digest_length` is `0` or larger than `OUTBYTES`.
+    pub fn new(digest_length: usize) -> Self {
+        Params::new().digest_length(digest_length).to_state()
+    }
+
+    /// Create a keyed hasher, whose digests are `digest_length` bytes long
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digest_length` is `0` or larger than `OUTBYTES`, or if
+    /// `key` is larger than `KEYBYTES`.
+    pub fn new_keyed(key: &[u8], digest_length: usize) -> Self {
+        Params::new()
+            .digest_length(digest_length)
+            .key(key)
+            .to_state()
+    }
+
+    /// Absorb `data`
+    pub fn update(&mut self, data: &[u8]) {
+        self.state.update(data);
+    }
+
+    /// Return the digest of the data absorbed so far
+    ///
+    /// The hasher is not modified: more data can be absorbed afterwards.
+    pub fn finalize(&self) -> Digest {
+        let mut digest = Digest {
+            bytes: [0; OUTBYTES],
+            len: self.digest_length,
+        };
+        let mut state = self.state;
+        state.finalize(&mut digest.bytes[..self.digest_length as usize]);
+        digest
+    }
+
+    /// Return the size of the digest, in bytes
+    pub fn digest_length(&self) -> usize {
+        self.digest_length as usize
+    }
+}
+
+impl Hasher for Blake2b {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.finalize();
+        let mut first = [0u8; 8];
+        let len = digest.as_bytes().len().min(first.len());
+        first[..len].copy_from_slice(&digest.as_bytes()[..len]);
+        u64::from_le_bytes(first)
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for Blake2b {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A BLAKE2b digest
+///
+/// Digests are compared in constant time, so that a MAC can be checked with
+/// `==`.
+#[derive(Clone, Copy)]
+pub struct Digest {
+    bytes: [u8; OUTBYTES],
+    len: u8,
+}
+
+impl Digest {
+    /// Return the digest as a byte slice
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+fn constant_time_eq(x: &[u8], y: &[u8]) -> bool {
+    if x.len() != y.len() {
+        return false;
+    }
+    x.iter().zip(y).fold(0u8, |d, (x, y)| d | (x ^ y)) == 0
+}
+
+impl PartialEq for Digest {
+    fn eq(&self, other: &Digest) -> bool {
+        constant_time_eq(self.as_bytes(), other.as_bytes())
+    }
+}
+
+impl Eq for Digest {}
+
+impl PartialEq<[u8]> for Digest {
+    fn eq(&self, other: &[u8]) -> bool {
+        constant_time_eq(self.as_bytes(), other)
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest(")?;
+        for b in self.as_bytes() {
+            write!(f, "{:02x}", b)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_rfc7693_vectors() {
+    // Appendix A
+    assert_eq!(
+        hash(b"abc"),
+        hex(
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        )[..]
+    );
+
+    // Appendix E: the hash of unkeyed and keyed digests, for various input
+    // and digest lengths
+    fn selftest_seq(len: usize, seed: u32) -> Vec<u8> {
+        let (mut a, mut b) = (0xdead_4badu32.wrapping_mul(seed), 1u32);
+        (0..len)
+            .map(|_| {
+                let t = a.wrapping_add(b);
+                a = b;
+                b = t;
+                (t >> 24) as u8
+            })
+            .collect()
+    }
+    let mut state = Blake2b::new(32);
+    for &outlen in &[20, 32, 48, 64] {
+        for &inlen in &[0, 3, 128, 129, 255, 1024] {
+            let input = selftest_seq(inlen, inlen as u32);
+            let key = selftest_seq(outlen, outlen as u32);
+            state.update(Params::new().digest_length(outlen).hash(&input).as_bytes());
+            let mut keyed = Blake2b::new_keyed(&key, outlen);
+            keyed.update(&input);
+            state.update(keyed.finalize().as_bytes());
+        }
+    }
+    assert_eq!(
+        state.finalize(),
+        hex("c23a7800d98123bd10f506c61e29da5603d763b8bbad2e737f5e765a7bccd475")[..]
+    );
+}
+
+#[test]
+fn test_params() {
+    let key: Vec<u8> = (0..64).collect();
+    assert_eq!(
+        Params::new().key(&key).hash(b""),
+        hex(
+            "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786\
+             b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568"
+        )[..]
+    );
+    let params = Params::new()
+        .digest_length(32)
+        .key(&key[..32])
+        .salt(b"minisign salt")
+        .personal(b"cache-key");
+    assert_eq!(
+        params.hash(b"The quick brown fox jumps over the lazy dog"),
+        hex("563ca0c86a68723e92c529a16eb5fc4ecd65c158557dee5f7c00e9f5e6e53017")[..]
+    );
+    assert_eq!(
+        Params::new()
+            .digest_length(20)
+            .salt(b"0123456789abcdef")
+            .personal(b"fedcba9876543210")
+            .hash(b"abc"),
+        hex("6b48a264dd49dee209e58f80aac57d58882896a3")[..]
+    );
+
+    // An empty key is no key
+    assert_eq!(Params::new().key(b"").hash(b"abc"), hash(b"abc"));
+    assert!(Params::new().key(b"k").hash(b"abc") != hash(b"abc"));
+    assert!(Params::new().digest_length(32).hash(b"abc") != hash(b"abc").as_bytes()[..32]);
+}
+
+#[test]
+fn test_incremental() {
+    let input: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+    let expected = Params::new().key(b"key").hash(&input);
+    let mut state = Blake2b::new_keyed(b"key", OUTBYTES);
+    for chunk in input.chunks(77) {
+        state.update(chunk);
+    }
+    assert_eq!(state.finalize(), expected);
+    #[cfg(feature = "std")]
+    {
+        use std::io::Write;
+
+        let mut writer = Blake2b::new_keyed(b"key", OUTBYTES);
+        for chunk in input.chunks(77) {
+            writer.write_all(chunk).unwrap();
+        }
+        assert_eq!(writer.finalize(), expected);
+    }
+
+    // Finalizing doesn't prevent absorbing more data
+    state.update(b"more");
+    let mut more = input.clone();
+    more.extend_from_slice(b"more");
+    assert_eq!(state.finalize(), Params::new().key(b"key").hash(&more));
+
+    // Hasher::finish() returns the first 8 bytes of the digest
+    let mut hasher = Blake2b::new(OUTBYTES);
+    Hasher::write(&mut hasher, &input);
+    let mut first = [0u8; 8];
+    first.copy_from_slice(&hash(&input).as_bytes()[..8]);
+    assert_eq!(hasher.finish(), u64::from_le_bytes(first));
+    let mut first = [0u8; 8];
+    first[..4].copy_from_slice(Blake2b::new(4).finalize().as_bytes());
+    assert_eq!(Blake2b::new(4).finish(), u64::from_le_bytes(first));
+}
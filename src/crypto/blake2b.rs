@@ -4,9 +4,17 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use super::cryptoutil::{copy_memory, read_u64v_le, write_u64v_le};
+use core::convert::TryInto;
 
-static IV: [u64; 8] = [
+#[cfg(any(
+    target_arch = "x86_64",
+    all(target_arch = "aarch64", target_feature = "neon")
+))]
+use super::blake2b_simd;
+use super::cryptoutil::{copy_memory, read_u64v_le, write_u32_le, write_u64_le, write_u64v_le};
+use crate::Error;
+
+pub(super) static IV: [u64; 8] = [
     0x6a09_e667_f3bc_c908,
     0xbb67_ae85_84ca_a73b,
     0x3c6e_f372_fe94_f82b,
@@ -17,7 +25,7 @@ static IV: [u64; 8] = [
     0x5be0_cd19_137e_2179,
 ];
 
-static SIGMA: [[usize; 16]; 12] = [
+pub(super) static SIGMA: [[usize; 16]; 12] = [
     [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
     [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
     [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
@@ -37,6 +45,7 @@ pub const BLAKE2B_OUTBYTES: usize = 64;
 pub const BLAKE2B_KEYBYTES: usize = 64;
 pub const BLAKE2B_SALTBYTES: usize = 16;
 pub const BLAKE2B_PERSONALBYTES: usize = 16;
+pub const BLAKE2B_STATEBYTES: usize = 8 * 8 + 2 * 8 + 2 * 8 + 2 + 2 * BLAKE2B_BLOCKBYTES;
 
 #[derive(Copy)]
 pub struct Blake2b {
@@ -51,6 +60,7 @@ pub struct Blake2b {
     digest_length: u8,
     computed: bool, // whether the final digest has been computed
     param: Blake2bParam,
+    backend: Backend,
 }
 
 impl Clone for Blake2b {
@@ -97,6 +107,135 @@ macro_rules! round( ($r:expr, $v:expr, $m:expr) => ( {
   }
 ));
 
+fn compress_portable(
+    h: &mut [u64; 8],
+    block: &[u8; BLAKE2B_BLOCKBYTES],
+    t: &[u64; 2],
+    f: &[u64; 2],
+) {
+    let mut ms: [u64; 16] = [0; 16];
+    let mut vs: [u64; 16] = [0; 16];
+
+    read_u64v_le(&mut ms, block);
+
+    for (v, h) in vs.iter_mut().zip(h.iter()) {
+        *v = *h;
+    }
+
+    vs[8] = IV[0];
+    vs[9] = IV[1];
+    vs[10] = IV[2];
+    vs[11] = IV[3];
+    vs[12] = t[0] ^ IV[4];
+    vs[13] = t[1] ^ IV[5];
+    vs[14] = f[0] ^ IV[6];
+    vs[15] = f[1] ^ IV[7];
+    round!(0, vs, ms);
+    round!(1, vs, ms);
+    round!(2, vs, ms);
+    round!(3, vs, ms);
+    round!(4, vs, ms);
+    round!(5, vs, ms);
+    round!(6, vs, ms);
+    round!(7, vs, ms);
+    round!(8, vs, ms);
+    round!(9, vs, ms);
+    round!(10, vs, ms);
+    round!(11, vs, ms);
+
+    for (h_elem, (v_low, v_high)) in h.iter_mut().zip(vs[0..8].iter().zip(vs[8..16].iter())) {
+        *h_elem = *h_elem ^ *v_low ^ *v_high;
+    }
+}
+
+/// An implementation of the compression function
+///
+/// All implementations compute exactly the same function. The fastest one
+/// supported by the CPU is selected when a hasher is created.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    Portable,
+    #[cfg(target_arch = "x86_64")]
+    Sse41,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    Neon,
+}
+
+impl Backend {
+    /// Return the fastest implementation supported by the current CPU
+    ///
+    /// With the `std` feature, x86_64 CPUs are probed at runtime. Otherwise,
+    /// only the features enabled at compile time are used. NEON is always
+    /// available on aarch64 targets that have it enabled.
+    pub fn detect() -> Backend {
+        #[cfg(all(target_arch = "x86_64", feature = "std"))]
+        {
+            if std::is_x86_feature_detected!("avx2") {
+                return Backend::Avx2;
+            }
+            if std::is_x86_feature_detected!("sse4.1") {
+                return Backend::Sse41;
+            }
+        }
+        #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+        {
+            if cfg!(target_feature = "avx2") {
+                return Backend::Avx2;
+            }
+            if cfg!(target_feature = "sse4.1") {
+                return Backend::Sse41;
+            }
+        }
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        {
+            return Backend::Neon;
+        }
+        #[allow(unreachable_code)]
+        Backend::Portable
+    }
+
+    /// Return `true` if the current CPU supports this implementation
+    pub fn is_supported(self) -> bool {
+        match self {
+            Backend::Portable => true,
+            #[cfg(all(target_arch = "x86_64", feature = "std"))]
+            Backend::Sse41 => std::is_x86_feature_detected!("sse4.1"),
+            #[cfg(all(target_arch = "x86_64", feature = "std"))]
+            Backend::Avx2 => std::is_x86_feature_detected!("avx2"),
+            #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+            Backend::Sse41 => cfg!(target_feature = "sse4.1"),
+            #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+            Backend::Avx2 => cfg!(target_feature = "avx2"),
+            #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+            Backend::Neon => true,
+        }
+    }
+
+    /// Compress a block into the chaining value `h`
+    ///
+    /// The implementation must be supported by the current CPU.
+    fn compress(
+        self,
+        h: &mut [u64; 8],
+        block: &[u8; BLAKE2B_BLOCKBYTES],
+        t: &[u64; 2],
+        f: &[u64; 2],
+    ) {
+        debug_assert!(self.is_supported());
+        match self {
+            Backend::Portable => compress_portable(h, block, t, f),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse41 => unsafe { blake2b_simd::sse41::compress(h, block, t, f) },
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => unsafe { blake2b_simd::avx2::compress(h, block, t, f) },
+            #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+            Backend::Neon => unsafe { blake2b_simd::neon::compress(h, block, t, f) },
+        }
+    }
+}
+
 impl Blake2b {
     fn set_lastnode(&mut self) {
         self.f[1] = 0xffff_ffff_ffff_ffff;
@@ -110,8 +249,8 @@ impl Blake2b {
     }
 
     fn increment_counter(&mut self, inc: u64) {
-        self.t[0] += inc;
-        self.t[1] += if self.t[0] < inc { 1 } else { 0 };
+        self.t[0] = self.t[0].wrapping_add(inc);
+        self.t[1] = self.t[1].wrapping_add(if self.t[0] < inc { 1 } else { 0 });
     }
 
     fn init0(param: Blake2bParam, digest_length: u8) -> Blake2b {
@@ -127,29 +266,23 @@ impl Blake2b {
             _key: [0; BLAKE2B_KEYBYTES],
             _key_length: 0u8,
             param,
+            backend: Backend::detect(),
         }
     }
 
     fn apply_param(&mut self) {
-        use std::io::Write;
-
-        use super::cryptoutil::WriteExt;
-
         let mut param_bytes: [u8; 64] = [0; 64];
-        {
-            let mut writer: &mut [u8] = &mut param_bytes;
-            writer.write_u8(self.param.digest_length).unwrap();
-            writer.write_u8(self.param.key_length).unwrap();
-            writer.write_u8(self.param.fanout).unwrap();
-            writer.write_u8(self.param.depth).unwrap();
-            writer.write_u32_le(self.param.leaf_length).unwrap();
-            writer.write_u64_le(self.param.node_offset).unwrap();
-            writer.write_u8(self.param.node_depth).unwrap();
-            writer.write_u8(self.param.inner_length).unwrap();
-            writer.write_all(&self.param.reserved).unwrap();
-            writer.write_all(&self.param.salt).unwrap();
-            writer.write_all(&self.param.personal).unwrap();
-        }
+        param_bytes[0] = self.param.digest_length;
+        param_bytes[1] = self.param.key_length;
+        param_bytes[2] = self.param.fanout;
+        param_bytes[3] = self.param.depth;
+        write_u32_le(&mut param_bytes[4..8], self.param.leaf_length);
+        write_u64_le(&mut param_bytes[8..16], self.param.node_offset);
+        param_bytes[16] = self.param.node_depth;
+        param_bytes[17] = self.param.inner_length;
+        param_bytes[18..32].copy_from_slice(&self.param.reserved);
+        param_bytes[32..48].copy_from_slice(&self.param.salt);
+        param_bytes[48..64].copy_from_slice(&self.param.personal);
 
         let mut param_words: [u64; 8] = [0; 8];
         read_u64v_le(&mut param_words, &param_bytes);
@@ -186,42 +319,47 @@ impl Blake2b {
         Blake2b::init_param(Blake2b::default_param(outlen as u8))
     }
 
-    fn compress(&mut self) {
-        let mut ms: [u64; 16] = [0; 16];
-        let mut vs: [u64; 16] = [0; 16];
-
-        read_u64v_le(&mut ms, &self.buf[0..BLAKE2B_BLOCKBYTES]);
-
-        for (v, h) in vs.iter_mut().zip(self.h.iter()) {
-            *v = *h;
+    /// Create a hasher with a key, a salt and a personalization string
+    ///
+    /// The key is absorbed as a first, zero-padded block, as specified in
+    /// RFC 7693. An empty key is the same as no key.
+    pub fn new_with_params(
+        outlen: usize,
+        key: &[u8],
+        salt: &[u8; BLAKE2B_SALTBYTES],
+        personal: &[u8; BLAKE2B_PERSONALBYTES],
+    ) -> Blake2b {
+        assert!(outlen > 0 && outlen <= BLAKE2B_OUTBYTES);
+        assert!(key.len() <= BLAKE2B_KEYBYTES);
+        let mut param = Blake2b::default_param(outlen as u8);
+        param.key_length = key.len() as u8;
+        param.salt = *salt;
+        param.personal = *personal;
+        let mut hasher = Blake2b::init_param(param);
+        if !key.is_empty() {
+            let mut block = [0u8; BLAKE2B_BLOCKBYTES];
+            block[..key.len()].copy_from_slice(key);
+            hasher.update(&block);
         }
+        hasher
+    }
 
-        vs[8] = IV[0];
-        vs[9] = IV[1];
-        vs[10] = IV[2];
-        vs[11] = IV[3];
-        vs[12] = self.t[0] ^ IV[4];
-        vs[13] = self.t[1] ^ IV[5];
-        vs[14] = self.f[0] ^ IV[6];
-        vs[15] = self.f[1] ^ IV[7];
-        round!(0, vs, ms);
-        round!(1, vs, ms);
-        round!(2, vs, ms);
-        round!(3, vs, ms);
-        round!(4, vs, ms);
-        round!(5, vs, ms);
-        round!(6, vs, ms);
-        round!(7, vs, ms);
-        round!(8, vs, ms);
-        round!(9, vs, ms);
-        round!(10, vs, ms);
-        round!(11, vs, ms);
-
-        for (h_elem, (v_low, v_high)) in
-            self.h.iter_mut().zip(vs[0..8].iter().zip(vs[8..16].iter()))
-        {
-            *h_elem = *h_elem ^ *v_low ^ *v_high;
+    fn compress(&mut self) {
+        let block = self.buf[..BLAKE2B_BLOCKBYTES].try_into().unwrap();
+        self.backend.compress(&mut self.h, block, &self.t, &self.f);
+    }
+
+    /// Use `backend` for the compression function, instead of the fastest
+    /// one supported by the CPU
+    ///
+    /// Returns `None` if the CPU doesn't support it.
+    #[cfg(test)]
+    pub fn with_backend(mut self, backend: Backend) -> Option<Blake2b> {
+        if !backend.is_supported() {
+            return None;
         }
+        self.backend = backend;
+        Some(self)
     }
 
     pub fn update(&mut self, mut input: &[u8]) {
@@ -280,9 +418,174 @@ impl Blake2b {
         copy_memory(&self.buf[0..outlen], out);
     }
 
+    fn counter(&self) -> u128 {
+        (self.t[1] as u128) << 64 | self.t[0] as u128
+    }
+
+    /// Return the number of bytes absorbed so far
+    ///
+    /// This can't overflow: `import_state()` rejects states whose counter is
+    /// too large.
+    pub fn absorbed(&self) -> u128 {
+        self.counter() + self.buflen as u128
+    }
+
+    /// Serialize the chaining value, the counters and the buffered data
+    ///
+    /// The parameter block is not included: the state can only be imported
+    /// into a hasher created with the same parameters.
+    pub fn export_state(&self) -> [u8; BLAKE2B_STATEBYTES] {
+        let mut state = [0u8; BLAKE2B_STATEBYTES];
+        let (h, rest) = state.split_at_mut(8 * 8);
+        let (t, rest) = rest.split_at_mut(2 * 8);
+        let (f, rest) = rest.split_at_mut(2 * 8);
+        let (buflen, buf) = rest.split_at_mut(2);
+        write_u64v_le(h, &self.h);
+        write_u64v_le(t, &self.t);
+        write_u64v_le(f, &self.f);
+        buflen.copy_from_slice(&(self.buflen as u16).to_le_bytes());
+        buf.copy_from_slice(&self.buf);
+        state
+    }
+
+    /// Create a hasher from a state previously returned by `export_state()`
+    ///
+    /// Returns `Error::InvalidEncoding` if the state is inconsistent, or if
+    /// it was exported after the hash was finalized.
+    pub fn import_state(outlen: usize, state: &[u8; BLAKE2B_STATEBYTES]) -> Result<Blake2b, Error> {
+        let mut hasher = Blake2b::new(outlen);
+        let (h, rest) = state.split_at(8 * 8);
+        let (t, rest) = rest.split_at(2 * 8);
+        let (f, rest) = rest.split_at(2 * 8);
+        let (buflen, buf) = rest.split_at(2);
+        read_u64v_le(&mut hasher.h, h);
+        read_u64v_le(&mut hasher.t, t);
+        read_u64v_le(&mut hasher.f, f);
+        hasher.buflen = u16::from_le_bytes([buflen[0], buflen[1]]) as usize;
+        hasher.buf.copy_from_slice(buf);
+        if hasher.f != [0, 0] || hasher.buflen > 2 * BLAKE2B_BLOCKBYTES {
+            return Err(Error::InvalidEncoding);
+        }
+        // Only full blocks are compressed before finalization, and a block is
+        // only compressed once more data follows it
+        let counter = hasher.counter();
+        if counter & (BLAKE2B_BLOCKBYTES as u128 - 1) != 0
+            || (counter != 0 && hasher.buflen <= BLAKE2B_BLOCKBYTES)
+            || counter.checked_add(hasher.buflen as u128).is_none()
+        {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(hasher)
+    }
+
     pub fn blake2b(out: &mut [u8], input: &[u8]) {
         let mut hasher: Blake2b = Blake2b::new(out.len());
         hasher.update(input);
         hasher.finalize(out);
     }
 }
+
+#[test]
+fn test_export_state() {
+    let input: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+    let mut expected = [0u8; BLAKE2B_OUTBYTES];
+    Blake2b::blake2b(&mut expected, &input);
+    for split in [0, 1, 127, 128, 129, 256, 257, 999, 1000] {
+        let mut hasher = Blake2b::new(BLAKE2B_OUTBYTES);
+        hasher.update(&input[..split]);
+        assert_eq!(hasher.absorbed(), split as u128);
+        let state = hasher.export_state();
+        let mut hasher = Blake2b::import_state(BLAKE2B_OUTBYTES, &state).unwrap();
+        hasher.update(&input[split..]);
+        let mut out = [0u8; BLAKE2B_OUTBYTES];
+        hasher.finalize(&mut out);
+        assert_eq!(out, expected);
+        let mut finalized = Blake2b::import_state(BLAKE2B_OUTBYTES, &state).unwrap();
+        finalized.finalize(&mut out);
+        assert!(Blake2b::import_state(BLAKE2B_OUTBYTES, &finalized.export_state()).is_err());
+    }
+
+    let mut hasher = Blake2b::new(BLAKE2B_OUTBYTES);
+    hasher.update(&input);
+    let state = hasher.export_state();
+    for t in [[1, 0], [u64::MAX, u64::MAX], [u64::MAX - 127, u64::MAX]] {
+        let mut crafted = state;
+        write_u64v_le(&mut crafted[64..80], &t);
+        match Blake2b::import_state(BLAKE2B_OUTBYTES, &crafted) {
+            Err(Error::InvalidEncoding) => {}
+            _ => panic!("Inconsistent counter accepted"),
+        }
+    }
+    let mut crafted = state;
+    crafted[96..98].copy_from_slice(&(BLAKE2B_BLOCKBYTES as u16).to_le_bytes());
+    assert!(Blake2b::import_state(BLAKE2B_OUTBYTES, &crafted).is_err());
+}
+
+/// Return the implementations supported by the current CPU
+#[cfg(test)]
+pub fn backends() -> Vec<Backend> {
+    let all = [
+        Backend::Portable,
+        #[cfg(target_arch = "x86_64")]
+        Backend::Sse41,
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2,
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        Backend::Neon,
+    ];
+    all.iter().copied().filter(|b| b.is_supported()).collect()
+}
+
+#[test]
+fn test_backends() {
+    assert!(backends().contains(&Backend::detect()));
+
+    // Compress pseudo-random states, blocks, counters and flags
+    let mut seed = [0u8; BLAKE2B_OUTBYTES];
+    for i in 0..200u32 {
+        Blake2b::blake2b(&mut seed, &i.to_le_bytes());
+        let mut words = [0u64; 8];
+        read_u64v_le(&mut words, &seed);
+        let mut block = [0u8; BLAKE2B_BLOCKBYTES];
+        let (lo, hi) = block.split_at_mut(64);
+        Blake2b::blake2b(lo, &seed);
+        Blake2b::blake2b(hi, lo);
+        let t = [words[0], if i % 2 == 0 { 0 } else { words[1] }];
+        let f = match i % 3 {
+            0 => [0, 0],
+            1 => [!0, 0],
+            _ => [!0, !0],
+        };
+        let mut expected = words;
+        compress_portable(&mut expected, &block, &t, &f);
+        for backend in backends() {
+            let mut h = words;
+            backend.compress(&mut h, &block, &t, &f);
+            assert_eq!(h, expected, "{:?}", backend);
+        }
+    }
+
+    // Hash messages of every length around the block boundaries, in one
+    // call and in uneven chunks
+    let input: Vec<u8> = (0..1031u32).map(|i| (i * 7 + i / 256) as u8).collect();
+    for len in (0..=600).chain(1000..=input.len()) {
+        let input = &input[..len];
+        let mut hasher = Blake2b::new(BLAKE2B_OUTBYTES)
+            .with_backend(Backend::Portable)
+            .unwrap();
+        hasher.update(input);
+        let mut expected = [0u8; BLAKE2B_OUTBYTES];
+        hasher.finalize(&mut expected);
+        for backend in backends() {
+            let mut hasher = Blake2b::new(BLAKE2B_OUTBYTES)
+                .with_backend(backend)
+                .unwrap();
+            for chunk in input.chunks(len % 131 + 1) {
+                hasher.update(chunk);
+            }
+            let mut out = [0u8; BLAKE2B_OUTBYTES];
+            hasher.finalize(&mut out);
+            assert_eq!(out, expected, "{:?}, {} bytes", backend, len);
+        }
+    }
+}
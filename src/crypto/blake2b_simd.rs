@@ -0,0 +1,478 @@
+//! Vectorized implementations of the BLAKE2b compression function
+//!
+//! They compute exactly the same function as the portable implementation.
+//! The 4x4 state matrix is kept in vector registers, one row at a time, so
+//! that the four column steps of a round run in parallel. Rows are then
+//! rotated so that the diagonals become columns, and rotated back after the
+//! diagonal steps.
+//!
+//! The second row is the last one computed by a step, so it stays in place:
+//! only the other rows are rotated, which keeps the rotations off the
+//! critical path.
+
+use super::blake2b::{BLAKE2B_BLOCKBYTES, IV, SIGMA};
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+use super::cryptoutil::read_u64v_le;
+
+/// Expand `$round` for each of the 12 rounds, so that the schedules are
+/// constant
+macro_rules! rounds {
+    ($round:ident) => {
+        $round!(0);
+        $round!(1);
+        $round!(2);
+        $round!(3);
+        $round!(4);
+        $round!(5);
+        $round!(6);
+        $round!(7);
+        $round!(8);
+        $round!(9);
+        $round!(10);
+        $round!(11);
+    };
+}
+
+/// Return the indices of the message words used by each lane, for the
+/// first and second half of the column step, then of the diagonal step
+///
+/// Lane `i` of the diagonal step uses the `i`-th word of the second row,
+/// so that row doesn't have to be rotated.
+#[inline(always)]
+fn schedule(s: &[usize; 16]) -> [[usize; 4]; 4] {
+    [
+        [s[0], s[2], s[4], s[6]],
+        [s[1], s[3], s[5], s[7]],
+        [s[14], s[8], s[10], s[12]],
+        [s[15], s[9], s[11], s[13]],
+    ]
+}
+
+/// Load a block as vectors of two consecutive message words
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn load_message(block: &[u8; BLAKE2B_BLOCKBYTES]) -> [core::arch::x86_64::__m128i; 8] {
+    use core::arch::x86_64::*;
+
+    let p = block.as_ptr() as *const __m128i;
+    let mut m = [_mm_setzero_si128(); 8];
+    for (i, m) in m.iter_mut().enumerate() {
+        *m = _mm_loadu_si128(p.add(i));
+    }
+    m
+}
+
+/// Return `[m[x], m[y]]` with a single instruction, from the message
+/// loaded by `load_message()`
+///
+/// `x` and `y` are constant once the rounds are expanded, so only one of
+/// the branches remains.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn pair(
+    m: &[core::arch::x86_64::__m128i; 8],
+    x: usize,
+    y: usize,
+) -> core::arch::x86_64::__m128i {
+    use core::arch::x86_64::*;
+
+    let (mx, my) = (m[x / 2], m[y / 2]);
+    match (x % 2, y % 2) {
+        (0, 0) => _mm_unpacklo_epi64(mx, my),
+        (1, 1) => _mm_unpackhi_epi64(mx, my),
+        (0, _) => _mm_blend_epi16(mx, my, 0xf0),
+        _ => _mm_alignr_epi8(my, mx, 8),
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub mod avx2 {
+    use core::arch::x86_64::*;
+    use core::ptr;
+
+    use super::*;
+
+    /// Rotate each 64-bit lane right by 24 bits
+    static ROT24: [u8; 32] = [
+        3, 4, 5, 6, 7, 0, 1, 2, 11, 12, 13, 14, 15, 8, 9, 10, //
+        3, 4, 5, 6, 7, 0, 1, 2, 11, 12, 13, 14, 15, 8, 9, 10,
+    ];
+
+    /// Rotate each 64-bit lane right by 16 bits
+    static ROT16: [u8; 32] = [
+        2, 3, 4, 5, 6, 7, 0, 1, 10, 11, 12, 13, 14, 15, 8, 9, //
+        2, 3, 4, 5, 6, 7, 0, 1, 10, 11, 12, 13, 14, 15, 8, 9,
+    ];
+
+    #[inline(always)]
+    unsafe fn load(src: &[u64]) -> __m256i {
+        _mm256_loadu_si256(src.as_ptr() as *const __m256i)
+    }
+
+    #[inline(always)]
+    unsafe fn store(dst: &mut [u64], x: __m256i) {
+        _mm256_storeu_si256(dst.as_mut_ptr() as *mut __m256i, x)
+    }
+
+    #[inline(always)]
+    unsafe fn gather(m: &[__m128i; 8], i: &[usize; 4]) -> __m256i {
+        let lo = _mm256_castsi128_si256(pair(m, i[0], i[1]));
+        _mm256_inserti128_si256(lo, pair(m, i[2], i[3]), 1)
+    }
+
+    #[inline(always)]
+    unsafe fn g(
+        a: &mut __m256i,
+        b: &mut __m256i,
+        c: &mut __m256i,
+        d: &mut __m256i,
+        (m0, m1): (__m256i, __m256i),
+        rot24: __m256i,
+        rot16: __m256i,
+    ) {
+        *a = _mm256_add_epi64(_mm256_add_epi64(*a, m0), *b);
+        *d = _mm256_shuffle_epi32(_mm256_xor_si256(*d, *a), 0b10_11_00_01);
+        *c = _mm256_add_epi64(*c, *d);
+        *b = _mm256_shuffle_epi8(_mm256_xor_si256(*b, *c), rot24);
+        *a = _mm256_add_epi64(_mm256_add_epi64(*a, m1), *b);
+        *d = _mm256_shuffle_epi8(_mm256_xor_si256(*d, *a), rot16);
+        *c = _mm256_add_epi64(*c, *d);
+        let x = _mm256_xor_si256(*b, *c);
+        *b = _mm256_or_si256(_mm256_srli_epi64(x, 63), _mm256_add_epi64(x, x));
+    }
+
+    /// Compress a block into the chaining value `h`
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn compress(
+        h: &mut [u64; 8],
+        block: &[u8; BLAKE2B_BLOCKBYTES],
+        t: &[u64; 2],
+        f: &[u64; 2],
+    ) {
+        let m = load_message(block);
+        // The masks are hidden from the optimizer, which would otherwise
+        // split the rotations into several shuffles of their inputs
+        let rot24 = _mm256_loadu_si256(ptr::read_volatile(&&ROT24).as_ptr() as *const __m256i);
+        let rot16 = _mm256_loadu_si256(ptr::read_volatile(&&ROT16).as_ptr() as *const __m256i);
+
+        let mut a = load(&h[0..4]);
+        let mut b = load(&h[4..8]);
+        let mut c = load(&IV[0..4]);
+        let mut d = _mm256_xor_si256(load(&IV[4..8]), load(&[t[0], t[1], f[0], f[1]]));
+        macro_rules! round {
+            ($r:expr) => {{
+                let i = schedule(&SIGMA[$r]);
+                let (m0, m1) = (gather(&m, &i[0]), gather(&m, &i[1]));
+                g(&mut a, &mut b, &mut c, &mut d, (m0, m1), rot24, rot16);
+                a = _mm256_permute4x64_epi64(a, 0b10_01_00_11);
+                c = _mm256_permute4x64_epi64(c, 0b00_11_10_01);
+                d = _mm256_permute4x64_epi64(d, 0b01_00_11_10);
+                let (m2, m3) = (gather(&m, &i[2]), gather(&m, &i[3]));
+                g(&mut a, &mut b, &mut c, &mut d, (m2, m3), rot24, rot16);
+                a = _mm256_permute4x64_epi64(a, 0b00_11_10_01);
+                c = _mm256_permute4x64_epi64(c, 0b10_01_00_11);
+                d = _mm256_permute4x64_epi64(d, 0b01_00_11_10);
+            }};
+        }
+        rounds!(round);
+        let lo = _mm256_xor_si256(load(&h[0..4]), _mm256_xor_si256(a, c));
+        let hi = _mm256_xor_si256(load(&h[4..8]), _mm256_xor_si256(b, d));
+        store(&mut h[0..4], lo);
+        store(&mut h[4..8], hi);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub mod sse41 {
+    use core::arch::x86_64::*;
+    use core::ptr;
+
+    use super::*;
+
+    /// Rotate each 64-bit lane right by 24 bits
+    static ROT24: [u8; 16] = [3, 4, 5, 6, 7, 0, 1, 2, 11, 12, 13, 14, 15, 8, 9, 10];
+
+    /// Rotate each 64-bit lane right by 16 bits
+    static ROT16: [u8; 16] = [2, 3, 4, 5, 6, 7, 0, 1, 10, 11, 12, 13, 14, 15, 8, 9];
+
+    /// A row of the state, as two vectors of two lanes
+    #[derive(Clone, Copy)]
+    struct Row(__m128i, __m128i);
+
+    #[inline(always)]
+    unsafe fn load(src: &[u64]) -> Row {
+        let p = src.as_ptr() as *const __m128i;
+        Row(_mm_loadu_si128(p), _mm_loadu_si128(p.add(1)))
+    }
+
+    #[inline(always)]
+    unsafe fn store(dst: &mut [u64], x: Row) {
+        let p = dst.as_mut_ptr() as *mut __m128i;
+        _mm_storeu_si128(p, x.0);
+        _mm_storeu_si128(p.add(1), x.1);
+    }
+
+    #[inline(always)]
+    unsafe fn gather(m: &[__m128i; 8], i: &[usize; 4]) -> Row {
+        Row(pair(m, i[0], i[1]), pair(m, i[2], i[3]))
+    }
+
+    #[inline(always)]
+    unsafe fn add(x: Row, y: Row) -> Row {
+        Row(_mm_add_epi64(x.0, y.0), _mm_add_epi64(x.1, y.1))
+    }
+
+    #[inline(always)]
+    unsafe fn xor(x: Row, y: Row) -> Row {
+        Row(_mm_xor_si128(x.0, y.0), _mm_xor_si128(x.1, y.1))
+    }
+
+    #[inline(always)]
+    unsafe fn rotr32(x: Row) -> Row {
+        Row(
+            _mm_shuffle_epi32(x.0, 0b10_11_00_01),
+            _mm_shuffle_epi32(x.1, 0b10_11_00_01),
+        )
+    }
+
+    #[inline(always)]
+    unsafe fn rotr_bytes(x: Row, mask: __m128i) -> Row {
+        Row(_mm_shuffle_epi8(x.0, mask), _mm_shuffle_epi8(x.1, mask))
+    }
+
+    #[inline(always)]
+    unsafe fn rotr63(x: Row) -> Row {
+        Row(
+            _mm_or_si128(_mm_srli_epi64(x.0, 63), _mm_add_epi64(x.0, x.0)),
+            _mm_or_si128(_mm_srli_epi64(x.1, 63), _mm_add_epi64(x.1, x.1)),
+        )
+    }
+
+    /// Rotate the lanes of a row left by one
+    #[inline(always)]
+    unsafe fn rotl1(x: Row) -> Row {
+        Row(_mm_alignr_epi8(x.1, x.0, 8), _mm_alignr_epi8(x.0, x.1, 8))
+    }
+
+    /// Rotate the lanes of a row left (or right) by two
+    #[inline(always)]
+    fn rotl2(x: Row) -> Row {
+        Row(x.1, x.0)
+    }
+
+    /// Rotate the lanes of a row right by one
+    #[inline(always)]
+    unsafe fn rotr1(x: Row) -> Row {
+        Row(_mm_alignr_epi8(x.0, x.1, 8), _mm_alignr_epi8(x.1, x.0, 8))
+    }
+
+    #[inline(always)]
+    unsafe fn g(
+        a: &mut Row,
+        b: &mut Row,
+        c: &mut Row,
+        d: &mut Row,
+        (m0, m1): (Row, Row),
+        rot24: __m128i,
+        rot16: __m128i,
+    ) {
+        *a = add(add(*a, m0), *b);
+        *d = rotr32(xor(*d, *a));
+        *c = add(*c, *d);
+        *b = rotr_bytes(xor(*b, *c), rot24);
+        *a = add(add(*a, m1), *b);
+        *d = rotr_bytes(xor(*d, *a), rot16);
+        *c = add(*c, *d);
+        *b = rotr63(xor(*b, *c));
+    }
+
+    /// Compress a block into the chaining value `h`
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support SSE4.1.
+    #[target_feature(enable = "sse4.1")]
+    pub unsafe fn compress(
+        h: &mut [u64; 8],
+        block: &[u8; BLAKE2B_BLOCKBYTES],
+        t: &[u64; 2],
+        f: &[u64; 2],
+    ) {
+        let m = load_message(block);
+        // See the AVX2 implementation
+        let rot24 = _mm_loadu_si128(ptr::read_volatile(&&ROT24).as_ptr() as *const __m128i);
+        let rot16 = _mm_loadu_si128(ptr::read_volatile(&&ROT16).as_ptr() as *const __m128i);
+
+        let mut a = load(&h[0..4]);
+        let mut b = load(&h[4..8]);
+        let mut c = load(&IV[0..4]);
+        let mut d = xor(load(&IV[4..8]), load(&[t[0], t[1], f[0], f[1]]));
+        macro_rules! round {
+            ($r:expr) => {{
+                let i = schedule(&SIGMA[$r]);
+                let (m0, m1) = (gather(&m, &i[0]), gather(&m, &i[1]));
+                g(&mut a, &mut b, &mut c, &mut d, (m0, m1), rot24, rot16);
+                a = rotr1(a);
+                c = rotl1(c);
+                d = rotl2(d);
+                let (m2, m3) = (gather(&m, &i[2]), gather(&m, &i[3]));
+                g(&mut a, &mut b, &mut c, &mut d, (m2, m3), rot24, rot16);
+                a = rotl1(a);
+                c = rotr1(c);
+                d = rotl2(d);
+            }};
+        }
+        rounds!(round);
+        let lo = xor(load(&h[0..4]), xor(a, c));
+        let hi = xor(load(&h[4..8]), xor(b, d));
+        store(&mut h[0..4], lo);
+        store(&mut h[4..8], hi);
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+pub mod neon {
+    use core::arch::aarch64::*;
+
+    use super::*;
+
+    /// A row of the state, as two vectors of two lanes
+    #[derive(Clone, Copy)]
+    struct Row(uint64x2_t, uint64x2_t);
+
+    #[inline(always)]
+    unsafe fn load(src: &[u64]) -> Row {
+        Row(vld1q_u64(src.as_ptr()), vld1q_u64(src.as_ptr().add(2)))
+    }
+
+    #[inline(always)]
+    unsafe fn store(dst: &mut [u64], x: Row) {
+        vst1q_u64(dst.as_mut_ptr(), x.0);
+        vst1q_u64(dst.as_mut_ptr().add(2), x.1);
+    }
+
+    #[inline(always)]
+    unsafe fn gather(m: &[u64; 16], i: &[usize; 4]) -> Row {
+        Row(
+            vcombine_u64(vcreate_u64(m[i[0]]), vcreate_u64(m[i[1]])),
+            vcombine_u64(vcreate_u64(m[i[2]]), vcreate_u64(m[i[3]])),
+        )
+    }
+
+    #[inline(always)]
+    unsafe fn add(x: Row, y: Row) -> Row {
+        Row(vaddq_u64(x.0, y.0), vaddq_u64(x.1, y.1))
+    }
+
+    #[inline(always)]
+    unsafe fn xor(x: Row, y: Row) -> Row {
+        Row(veorq_u64(x.0, y.0), veorq_u64(x.1, y.1))
+    }
+
+    #[inline(always)]
+    unsafe fn rotr32(x: Row) -> Row {
+        Row(
+            vreinterpretq_u64_u32(vrev64q_u32(vreinterpretq_u32_u64(x.0))),
+            vreinterpretq_u64_u32(vrev64q_u32(vreinterpretq_u32_u64(x.1))),
+        )
+    }
+
+    #[inline(always)]
+    unsafe fn rotr24(x: Row) -> Row {
+        Row(
+            vsriq_n_u64::<24>(vshlq_n_u64::<40>(x.0), x.0),
+            vsriq_n_u64::<24>(vshlq_n_u64::<40>(x.1), x.1),
+        )
+    }
+
+    #[inline(always)]
+    unsafe fn rotr16(x: Row) -> Row {
+        Row(
+            vsriq_n_u64::<16>(vshlq_n_u64::<48>(x.0), x.0),
+            vsriq_n_u64::<16>(vshlq_n_u64::<48>(x.1), x.1),
+        )
+    }
+
+    #[inline(always)]
+    unsafe fn rotr63(x: Row) -> Row {
+        Row(
+            vsriq_n_u64::<63>(vshlq_n_u64::<1>(x.0), x.0),
+            vsriq_n_u64::<63>(vshlq_n_u64::<1>(x.1), x.1),
+        )
+    }
+
+    /// Rotate the lanes of a row left by one
+    #[inline(always)]
+    unsafe fn rotl1(x: Row) -> Row {
+        Row(vextq_u64::<1>(x.0, x.1), vextq_u64::<1>(x.1, x.0))
+    }
+
+    /// Rotate the lanes of a row left (or right) by two
+    #[inline(always)]
+    fn rotl2(x: Row) -> Row {
+        Row(x.1, x.0)
+    }
+
+    /// Rotate the lanes of a row right by one
+    #[inline(always)]
+    unsafe fn rotr1(x: Row) -> Row {
+        Row(vextq_u64::<1>(x.1, x.0), vextq_u64::<1>(x.0, x.1))
+    }
+
+    #[inline(always)]
+    unsafe fn g(a: &mut Row, b: &mut Row, c: &mut Row, d: &mut Row, (m0, m1): (Row, Row)) {
+        *a = add(add(*a, m0), *b);
+        *d = rotr32(xor(*d, *a));
+        *c = add(*c, *d);
+        *b = rotr24(xor(*b, *c));
+        *a = add(add(*a, m1), *b);
+        *d = rotr16(xor(*d, *a));
+        *c = add(*c, *d);
+        *b = rotr63(xor(*b, *c));
+    }
+
+    /// Compress a block into the chaining value `h`
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support NEON.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn compress(
+        h: &mut [u64; 8],
+        block: &[u8; BLAKE2B_BLOCKBYTES],
+        t: &[u64; 2],
+        f: &[u64; 2],
+    ) {
+        let mut m = [0u64; 16];
+        read_u64v_le(&mut m, block);
+
+        let mut a = load(&h[0..4]);
+        let mut b = load(&h[4..8]);
+        let mut c = load(&IV[0..4]);
+        let mut d = xor(load(&IV[4..8]), load(&[t[0], t[1], f[0], f[1]]));
+        macro_rules! round {
+            ($r:expr) => {{
+                let i = schedule(&SIGMA[$r]);
+                let (m0, m1) = (gather(&m, &i[0]), gather(&m, &i[1]));
+                g(&mut a, &mut b, &mut c, &mut d, (m0, m1));
+                a = rotr1(a);
+                c = rotl1(c);
+                d = rotl2(d);
+                let (m2, m3) = (gather(&m, &i[2]), gather(&m, &i[3]));
+                g(&mut a, &mut b, &mut c, &mut d, (m2, m3));
+                a = rotl1(a);
+                c = rotr1(c);
+                d = rotl2(d);
+            }};
+        }
+        rounds!(round);
+        let lo = xor(load(&h[0..4]), xor(a, c));
+        let hi = xor(load(&h[4..8]), xor(b, d));
+        store(&mut h[0..4], lo);
+        store(&mut h[4..8], hi);
+    }
+}
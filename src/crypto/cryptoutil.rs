@@ -8,8 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::ptr;
-use std::{io, mem::MaybeUninit};
+use core::mem::MaybeUninit;
+use core::ptr;
 
 pub fn write_u64_le(dst: &mut [u8], mut input: u64) {
     assert!(dst.len() == 8);
@@ -68,30 +68,10 @@ pub fn copy_memory(src: &[u8], dst: &mut [u8]) {
     }
 }
 
-pub trait WriteExt {
-    fn write_u8(&mut self, val: u8) -> io::Result<()>;
-    fn write_u32_le(&mut self, val: u32) -> io::Result<()>;
-    fn write_u64_le(&mut self, val: u64) -> io::Result<()>;
-}
-
-impl<T> WriteExt for T
-where
-    T: io::Write,
-{
-    fn write_u8(&mut self, val: u8) -> io::Result<()> {
-        let buff = [val];
-        self.write_all(&buff)
-    }
-
-    fn write_u32_le(&mut self, val: u32) -> io::Result<()> {
-        let mut buff = [0u8; 4];
-        write_u32_le(&mut buff, val);
-        self.write_all(&buff)
-    }
-
-    fn write_u64_le(&mut self, val: u64) -> io::Result<()> {
-        let mut buff = [0u8; 8];
-        write_u64_le(&mut buff, val);
-        self.write_all(&buff)
+/// Overwrite `bin` with zeros, in a way the compiler won't optimize away
+#[cfg(feature = "secret-key")]
+pub fn wipe(bin: &mut [u8]) {
+    for b in bin.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
     }
 }
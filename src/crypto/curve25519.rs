@@ -1,8 +1,10 @@
 #![allow(unused_parens)]
 #![allow(non_camel_case_types)]
+#![allow(clippy::needless_range_loop, clippy::wrong_self_convention)]
 
-use std::cmp::{min, Eq, PartialEq};
-use std::ops::{Add, Mul, Sub};
+use alloc::vec::Vec;
+use core::cmp::{min, Eq, PartialEq};
+use core::ops::{Add, Mul, Sub};
 
 pub type fiat_25519_u1 = u8;
 pub type fiat_25519_i1 = i8;
@@ -708,25 +710,20 @@ impl GeP2 {
     }
 
     pub fn double_scalarmult_vartime(a_scalar: &[u8], a_point: GeP3, b_scalar: &[u8]) -> GeP2 {
+        let ai = a_point.odd_multiples();
+        GeP2::double_scalarmult_precomputed_vartime(a_scalar, &ai, b_scalar)
+    }
+
+    /// Compute `a_scalar * A + b_scalar * B`, `ai` being the odd multiples
+    /// of `A`, as returned by `GeP3::odd_multiples()`
+    pub fn double_scalarmult_precomputed_vartime(
+        a_scalar: &[u8],
+        ai: &[GeCached; 8],
+        b_scalar: &[u8],
+    ) -> GeP2 {
         let aslide = GeP2::slide(a_scalar);
         let bslide = GeP2::slide(b_scalar);
 
-        let mut ai = [GeCached {
-            y_plus_x: FE_ZERO,
-            y_minus_x: FE_ZERO,
-            z: FE_ZERO,
-            t2d: FE_ZERO,
-        }; 8]; // A,3A,5A,7A,9A,11A,13A,15A
-        ai[0] = a_point.to_cached();
-        let a2 = a_point.dbl().to_p3();
-        ai[1] = (a2 + ai[0]).to_p3().to_cached();
-        ai[2] = (a2 + ai[1]).to_p3().to_cached();
-        ai[3] = (a2 + ai[2]).to_p3().to_cached();
-        ai[4] = (a2 + ai[3]).to_p3().to_cached();
-        ai[5] = (a2 + ai[4]).to_p3().to_cached();
-        ai[6] = (a2 + ai[5]).to_p3().to_cached();
-        ai[7] = (a2 + ai[6]).to_p3().to_cached();
-
         let mut r = GeP2::zero();
 
         let mut i: usize = 255;
@@ -762,6 +759,69 @@ impl GeP2 {
             i -= 1;
         }
     }
+
+    /// Compute `b_scalar * B + sum(scalar * point)` over all the `terms`,
+    /// sharing the doublings between all the points
+    pub fn multiscalar_mul_vartime(b_scalar: &[u8], terms: &[([u8; 32], GeP3)]) -> GeP2 {
+        let bslide = GeP2::slide(b_scalar);
+        let slides: Vec<[i8; 256]> = terms.iter().map(|(s, _)| GeP2::slide(s)).collect();
+        let tables: Vec<[GeCached; 8]> = terms.iter().map(|(_, p)| p.odd_multiples()).collect();
+
+        let mut r = GeP2::zero();
+
+        let mut i: usize = 255;
+        loop {
+            if bslide[i] != 0 || slides.iter().any(|slide| slide[i] != 0) {
+                break;
+            }
+            if i == 0 {
+                return r;
+            }
+            i -= 1;
+        }
+
+        loop {
+            let mut t = r.dbl();
+            for (slide, table) in slides.iter().zip(tables.iter()) {
+                if slide[i] > 0 {
+                    t = t.to_p3() + table[(slide[i] / 2) as usize];
+                } else if slide[i] < 0 {
+                    t = t.to_p3() - table[(-slide[i] / 2) as usize];
+                }
+            }
+
+            if bslide[i] > 0 {
+                t = t.to_p3() + BI[(bslide[i] / 2) as usize];
+            } else if bslide[i] < 0 {
+                t = t.to_p3() - BI[(-bslide[i] / 2) as usize];
+            }
+
+            r = t.to_p2();
+
+            if i == 0 {
+                return r;
+            }
+            i -= 1;
+        }
+    }
+
+    /// Compute `self + q`
+    pub fn add_p3(&self, q: &GeP3) -> GeP2 {
+        let p = GeP3 {
+            x: self.x * self.z,
+            y: self.y * self.z,
+            z: self.z.square(),
+            t: self.x * self.y,
+        };
+        (p + q.to_cached()).to_p2()
+    }
+
+    /// Return `true` if multiplying the point by the cofactor gives the
+    /// identity
+    pub fn is_small_order(&self) -> bool {
+        let r = self.dbl().to_p2().dbl().to_p2().dbl().to_p2();
+        !r.x.is_nonzero() && !(r.y - r.z).is_nonzero()
+    }
 }
 
 impl GeP3 {
@@ -812,6 +872,85 @@ impl GeP3 {
     fn dbl(&self) -> GeP1P1 {
         self.to_p2().dbl()
     }
+
+    /// Return `true` if multiplying the point by the cofactor gives the
+    /// identity
+    pub fn is_small_order(&self) -> bool {
+        self.to_p2().is_small_order()
+    }
+
+    /// Return the odd multiples `P, 3P, 5P, ..., 15P` of the point
+    pub fn odd_multiples(&self) -> [GeCached; 8] {
+        let mut ai = [GeCached {
+            y_plus_x: FE_ZERO,
+            y_minus_x: FE_ZERO,
+            z: FE_ZERO,
+            t2d: FE_ZERO,
+        }; 8];
+        ai[0] = self.to_cached();
+        let a2 = self.dbl().to_p3();
+        for i in 1..8 {
+            ai[i] = (a2 + ai[i - 1]).to_p3().to_cached();
+        }
+        ai
+    }
+}
+
+#[cfg(feature = "secret-key")]
+impl Fe {
+    fn cmov(&mut self, other: &Fe, flag: fiat_25519_u1) {
+        for (h, g) in self.0.iter_mut().zip(other.0.iter()) {
+            let h0 = *h;
+            fiat_25519_cmovznz_u64(h, flag, h0, *g);
+        }
+    }
+}
+
+#[cfg(feature = "secret-key")]
+impl GeP3 {
+    fn identity() -> GeP3 {
+        GeP3 {
+            x: FE_ZERO,
+            y: FE_ONE,
+            z: FE_ONE,
+            t: FE_ZERO,
+        }
+    }
+
+    fn base() -> GeP3 {
+        let mut b = [0x66u8; 32];
+        b[0] = 0x58;
+        let minus_b = GeP3::from_bytes_negate_vartime(&b).unwrap();
+        GeP3 {
+            x: minus_b.x.neg(),
+            t: minus_b.t.neg(),
+            ..minus_b
+        }
+    }
+
+    fn cmov(&mut self, other: &GeP3, flag: fiat_25519_u1) {
+        self.x.cmov(&other.x, flag);
+        self.y.cmov(&other.y, flag);
+        self.z.cmov(&other.z, flag);
+        self.t.cmov(&other.t, flag);
+    }
+
+    /// Compute `scalar * B` in constant time
+    pub fn scalarmult_base(scalar: &[u8]) -> GeP3 {
+        let b = GeP3::base().to_cached();
+        let mut r = GeP3::identity();
+        for i in (0..256).rev() {
+            let bit = (scalar[i >> 3] >> (i & 7)) & 1;
+            r = r.dbl().to_p3();
+            let rb = (r + b).to_p3();
+            r.cmov(&rb, bit);
+        }
+        r
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.to_p2().to_bytes()
+    }
 }
 
 impl Add<GeCached> for GeP3 {
@@ -1216,6 +1355,41 @@ pub fn sc_reduce(s: &mut [u8]) {
     s[31] = (s11 >> 17) as u8;
 }
 
+/// Compute `(a * b + c) mod L`
+pub fn sc_muladd(s: &mut [u8], a: &[u8], b: &[u8], c: &[u8]) {
+    let mut acc = [0u64; 64];
+    for (i, &ai) in a[..32].iter().enumerate() {
+        for (j, &bj) in b[..32].iter().enumerate() {
+            acc[i + j] += ai as u64 * bj as u64;
+        }
+    }
+    for (x, &ci) in acc.iter_mut().zip(c[..32].iter()) {
+        *x += ci as u64;
+    }
+    let mut t = [0u8; 64];
+    let mut carry = 0u64;
+    for (ti, x) in t.iter_mut().zip(acc.iter()) {
+        let v = x + carry;
+        *ti = v as u8;
+        carry = v >> 8;
+    }
+    sc_reduce(&mut t);
+    s[..32].copy_from_slice(&t[..32]);
+}
+
+/// Return `true` if `s` is the canonical encoding of a point: `y` is
+/// reduced, and the sign bit is not set if `x` is zero
+pub fn is_canonical(s: &[u8]) -> bool {
+    let mut y = [0u8; 32];
+    y.copy_from_slice(&s[0..32]);
+    y[31] &= 0x7f;
+    let fe_y = Fe::from_bytes(&y);
+    if fe_y.to_bytes() != y {
+        return false;
+    }
+    s[31] & 0x80 == 0 || (fe_y.square() - FE_ONE).is_nonzero()
+}
+
 pub fn is_identity(s: &[u8]) -> bool {
     let mut c = s[0] ^ 0x01;
     for i in 1..31 {
@@ -1,9 +1,23 @@
-use super::curve25519::{is_identity, sc_reduce, GeP2, GeP3};
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(feature = "secret-key")]
+use super::cryptoutil::wipe;
+use super::curve25519::{is_canonical, is_identity, sc_muladd, sc_reduce, GeCached, GeP2, GeP3};
 use super::sha512;
 
+/// The maximum number of signatures combined into a single multiscalar
+/// multiplication
+const BATCH_SIZE: usize = 64;
+
+/// Domain separation for the transcript the batch coefficients are derived
+/// from
+const BATCH_DOMAIN: &[u8] = b"minisign-verify ed25519 batch";
+
+/// The order of the main subgroup, in little-endian, like `S`
 static L: [u8; 32] = [
-    0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x14, 0xde, 0xf9, 0xde, 0xa2, 0xf7, 0x9c, 0xd6, 0x58, 0x12, 0x63, 0x1a, 0x5c, 0xf5, 0xd3, 0xed,
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
 ];
 
 fn check_s_lt_l(s: &[u8]) -> bool {
@@ -24,33 +38,510 @@ fn check_s_lt_l(s: &[u8]) -> bool {
     c == 0
 }
 
-pub fn verify(message: &[u8], public_key: &[u8], signature: &[u8]) -> bool {
-    if check_s_lt_l(&signature[32..64]) || is_identity(public_key) {
-        return false;
+/// The rules deciding whether an Ed25519 signature is valid
+///
+/// Honest signers never produce signatures whose validity depends on the
+/// mode. But implementations disagree on edge cases, such as non-canonical
+/// encodings and points of small order, which matters when several
+/// verifiers must reach the same decision.
+///
+/// In all modes, `S` must be canonical (lower than the group order).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ValidationMode {
+    /// The default rules
+    ///
+    /// These are the rules of previous versions of this crate, except that
+    /// they accepted a non-canonical `S` by mistake.
+    ///
+    /// The public key is rejected if it is the canonical encoding of the
+    /// identity or all zeros, but may be non-canonical or have a small
+    /// order. The equation is cofactorless, and `R` must be identical to the
+    /// canonical encoding of `[S]B - [k]A`.
+    #[default]
+    Classic,
+    /// Strict RFC 8032 verification
+    ///
+    /// `A` and `R` must be canonically encoded, and not of small order. The
+    /// equation is cofactorless: `[S]B = R + [k]A`.
+    Strict,
+    /// ZIP-215 rules, as used by Zcash and other consensus systems
+    ///
+    /// `A` and `R` may be non-canonically encoded, and have any order. The
+    /// equation is cofactored: `[8][S]B = [8]R + [8][k]A`.
+    Zip215,
+}
+
+/// Return `true` if the encoding of `A`, decoded as `-A`, is acceptable in
+/// `mode`
+fn is_acceptable_public_key(public_key: &[u8], minus_a: &GeP3, mode: ValidationMode) -> bool {
+    match mode {
+        ValidationMode::Classic => {
+            !is_identity(public_key) && public_key.iter().fold(0, |acc, x| acc | x) != 0
+        }
+        ValidationMode::Strict => is_canonical(public_key) && !minus_a.is_small_order(),
+        ValidationMode::Zip215 => true,
+    }
+}
+
+/// Decode `R`, returning `-R` if its encoding is acceptable in `mode`
+fn decode_r(r: &[u8], mode: ValidationMode) -> Option<GeP3> {
+    let minus_r = GeP3::from_bytes_negate_vartime(r)?;
+    let acceptable = match mode {
+        ValidationMode::Classic => is_canonical(r),
+        ValidationMode::Strict => is_canonical(r) && !minus_r.is_small_order(),
+        ValidationMode::Zip215 => true,
+    };
+    if acceptable {
+        Some(minus_r)
+    } else {
+        None
+    }
+}
+
+/// A public key decoded once, along with a table of multiples of `-A`,
+/// to speed up repeated verifications with the same key
+#[derive(Clone)]
+pub struct PreparedKey {
+    public_key: [u8; 32],
+    /// `None` if the key is not a point: every signature is then rejected
+    minus_a: Option<[GeCached; 8]>,
+    classic: bool,
+    strict: bool,
+}
+
+impl PreparedKey {
+    pub fn new(public_key: &[u8]) -> PreparedKey {
+        let mut pk = [0u8; 32];
+        pk.copy_from_slice(public_key);
+        let minus_a = GeP3::from_bytes_negate_vartime(public_key);
+        let is_acceptable = |mode| match &minus_a {
+            Some(minus_a) => is_acceptable_public_key(public_key, minus_a, mode),
+            None => false,
+        };
+        PreparedKey {
+            public_key: pk,
+            classic: is_acceptable(ValidationMode::Classic),
+            strict: is_acceptable(ValidationMode::Strict),
+            minus_a: minus_a.map(|minus_a| minus_a.odd_multiples()),
+        }
+    }
+
+    /// Return the multiples of `-A` if the key is acceptable in `mode`
+    fn minus_a(&self, mode: ValidationMode) -> Option<&[GeCached; 8]> {
+        let acceptable = match mode {
+            ValidationMode::Classic => self.classic,
+            ValidationMode::Strict => self.strict,
+            ValidationMode::Zip215 => true,
+        };
+        self.minus_a.as_ref().filter(|_| acceptable)
+    }
+}
+
+/// An incremental Ed25519 verifier
+///
+/// The message can be absorbed in multiple chunks, without ever being
+/// entirely held in memory, since it is only used to compute
+/// `SHA512(R || A || M)`.
+#[derive(Clone, Copy)]
+pub struct Verifier {
+    hasher: sha512::Hash,
+    public_key: [u8; 32],
+    signature: [u8; 64],
+}
+
+impl Verifier {
+    pub fn new(public_key: &[u8], signature: &[u8]) -> Verifier {
+        let mut hasher = sha512::Hash::new();
+        hasher.update(&signature[0..32]);
+        hasher.update(public_key);
+        let mut pk = [0u8; 32];
+        pk.copy_from_slice(public_key);
+        let mut sig = [0u8; 64];
+        sig.copy_from_slice(signature);
+        Verifier {
+            hasher,
+            public_key: pk,
+            signature: sig,
+        }
+    }
+
+    /// Absorb a chunk of the message
+    pub fn update(&mut self, message: &[u8]) {
+        self.hasher.update(message);
+    }
+
+    /// Check the encodings of `S`, `A` and `R`, and return `-A` and `-R`
+    /// along with the reduced `SHA512(R || A || M)`
+    fn challenge(&self, mode: ValidationMode) -> Option<(GeP3, GeP3, [u8; 64])> {
+        if check_s_lt_l(&self.signature[32..64]) {
+            return None;
+        }
+        let minus_a = GeP3::from_bytes_negate_vartime(&self.public_key)
+            .filter(|minus_a| is_acceptable_public_key(&self.public_key, minus_a, mode))?;
+        let minus_r = decode_r(&self.signature[0..32], mode)?;
+        let mut hash = self.hasher.finalize();
+        sc_reduce(&mut hash);
+        Some((minus_a, minus_r, hash))
     }
 
-    let a = match GeP3::from_bytes_negate_vartime(public_key) {
-        Some(g) => g,
-        None => {
+    /// Return `true` if the signature is valid for the absorbed message,
+    /// according to the rules of `mode`
+    pub fn finalize(self, mode: ValidationMode) -> bool {
+        let minus_a = match GeP3::from_bytes_negate_vartime(&self.public_key)
+            .filter(|minus_a| is_acceptable_public_key(&self.public_key, minus_a, mode))
+        {
+            Some(minus_a) => minus_a,
+            None => return false,
+        };
+        self.check_equation(mode, |hash, s| {
+            GeP2::double_scalarmult_vartime(hash, minus_a, s)
+        })
+    }
+
+    /// Return `true` if the signature is valid for the absorbed message,
+    /// using `key`, which must have been prepared from the public key the
+    /// verifier was created with
+    pub fn finalize_prepared(self, key: &PreparedKey, mode: ValidationMode) -> bool {
+        debug_assert!(key.public_key == self.public_key);
+        let minus_a = match key.minus_a(mode) {
+            Some(minus_a) => minus_a,
+            None => return false,
+        };
+        self.check_equation(mode, |hash, s| {
+            GeP2::double_scalarmult_precomputed_vartime(hash, minus_a, s)
+        })
+    }
+
+    /// Return `true` if the signature is valid for the absorbed message,
+    /// using `key` if it is available, or decoding the public key otherwise
+    pub fn finalize_with(self, key: Option<&PreparedKey>, mode: ValidationMode) -> bool {
+        match key {
+            Some(key) => self.finalize_prepared(key, mode),
+            None => self.finalize(mode),
+        }
+    }
+
+    /// Check `S` and `R`, and the verification equation once the public key
+    /// has been accepted, `double_scalarmult` computing `[k](-A) + [S]B`
+    fn check_equation<F>(self, mode: ValidationMode, double_scalarmult: F) -> bool
+    where
+        F: FnOnce(&[u8], &[u8]) -> GeP2,
+    {
+        let signature = &self.signature;
+        if check_s_lt_l(&signature[32..64]) {
             return false;
         }
-    };
-    if public_key.iter().fold(0, |acc, x| acc | x) == 0 {
-        return false;
+        let minus_r = match mode {
+            ValidationMode::Classic => None,
+            _ => match decode_r(&signature[0..32], mode) {
+                Some(minus_r) => Some(minus_r),
+                None => return false,
+            },
+        };
+        let mut hash = self.hasher.finalize();
+        sc_reduce(&mut hash);
+
+        let r = double_scalarmult(hash.as_ref(), &signature[32..64]);
+        match (mode, minus_r) {
+            (ValidationMode::Zip215, Some(minus_r)) => r.add_p3(&minus_r).is_small_order(),
+            _ => {
+                r.to_bytes()
+                    .as_ref()
+                    .iter()
+                    .zip(signature.iter())
+                    .fold(0, |acc, (x, y)| acc | (x ^ y))
+                    == 0
+            }
+        }
+    }
+
+    /// Verify many signatures at once, returning whether each of them is
+    /// valid according to the rules of `mode`
+    ///
+    /// In `ValidationMode::Zip215`, up to `BATCH_SIZE` signatures are checked
+    /// together with a single multiscalar multiplication, as a random linear
+    /// combination whose coefficients are derived from a hash of the whole
+    /// batch. If the combined check fails, the signatures of that batch are
+    /// verified one by one to find the invalid ones.
+    ///
+    /// The combined check is cofactored, so it would accept signatures with
+    /// a small-order component that the cofactorless equation of the other
+    /// modes rejects. In these modes, the signatures are always verified one
+    /// by one.
+    pub fn finalize_batch(verifiers: &[Verifier], mode: ValidationMode) -> Vec<bool> {
+        if mode != ValidationMode::Zip215 {
+            return verifiers
+                .iter()
+                .map(|verifier| verifier.finalize(mode))
+                .collect();
+        }
+        let mut valid = vec![false; verifiers.len()];
+        for (verifiers, valid) in verifiers
+            .chunks(BATCH_SIZE)
+            .zip(valid.chunks_mut(BATCH_SIZE))
+        {
+            Verifier::finalize_chunk(verifiers, valid, mode);
+        }
+        valid
     }
 
+    fn finalize_chunk(verifiers: &[Verifier], valid: &mut [bool], mode: ValidationMode) {
+        // Signatures with invalid encodings are rejected upfront, and
+        // remain marked as invalid
+        let mut transcript = sha512::Hash::new();
+        transcript.update(BATCH_DOMAIN);
+        let mut prepared = Vec::with_capacity(verifiers.len());
+        for (i, verifier) in verifiers.iter().enumerate() {
+            let (minus_a, minus_r, hash) = match verifier.challenge(mode) {
+                Some(challenge) => challenge,
+                None => continue,
+            };
+            transcript.update(verifier.signature);
+            transcript.update(verifier.public_key);
+            transcript.update(&hash[0..32]);
+            prepared.push((i, minus_a, minus_r, hash));
+        }
+        if prepared.len() < 2 {
+            for &(i, ..) in &prepared {
+                valid[i] = verifiers[i].finalize(mode);
+            }
+            return;
+        }
+        let transcript = transcript.finalize();
+
+        // sum(z * S) * B + sum(z * h * -A) + sum(z * -R) must be the identity,
+        // with the terms of identical public keys merged
+        let mut b_scalar = [0u8; 32];
+        let mut terms: Vec<([u8; 32], GeP3)> = Vec::with_capacity(prepared.len() + 1);
+        let mut keys: Vec<(&[u8; 32], usize)> = Vec::new();
+        for (j, (i, minus_a, minus_r, hash)) in prepared.iter().enumerate() {
+            let verifier = &verifiers[*i];
+            let mut z = [0u8; 32];
+            let mut hasher = sha512::Hash::new();
+            hasher.update(transcript);
+            hasher.update((j as u64).to_le_bytes());
+            z[0..16].copy_from_slice(&hasher.finalize()[0..16]);
+
+            let acc = b_scalar;
+            sc_muladd(&mut b_scalar, &z, &verifier.signature[32..64], &acc);
+            let k = match keys.iter().find(|(pk, _)| **pk == verifier.public_key) {
+                Some(&(_, k)) => k,
+                None => {
+                    terms.push(([0u8; 32], *minus_a));
+                    keys.push((&verifier.public_key, terms.len() - 1));
+                    terms.len() - 1
+                }
+            };
+            let acc = terms[k].0;
+            sc_muladd(&mut terms[k].0, &z, &hash[0..32], &acc);
+            terms.push((z, *minus_r));
+        }
+        let batch_valid = GeP2::multiscalar_mul_vartime(&b_scalar, &terms).is_small_order();
+        for &(i, ..) in &prepared {
+            valid[i] = batch_valid || verifiers[i].finalize(mode);
+        }
+    }
+}
+
+/// Derive a key pair from a 32-byte seed
+///
+/// The secret key is the seed followed by the public key.
+#[cfg(feature = "secret-key")]
+pub fn keypair(seed: &[u8; 32]) -> ([u8; 64], [u8; 32]) {
+    let mut az = sha512::Hash::new();
+    az.update(seed);
+    let mut az = az.finalize();
+    az[0] &= 248;
+    az[31] &= 63;
+    az[31] |= 64;
+    let public_key = GeP3::scalarmult_base(&az[0..32]).to_bytes();
+    wipe(&mut az);
+    let mut secret_key = [0u8; 64];
+    secret_key[0..32].copy_from_slice(seed);
+    secret_key[32..64].copy_from_slice(&public_key);
+    (secret_key, public_key)
+}
+
+#[cfg(feature = "sign")]
+pub fn signature(message: &[u8], secret_key: &[u8; 64]) -> [u8; 64] {
+    let seed = &secret_key[0..32];
+    let public_key = &secret_key[32..64];
+    let mut az = sha512::Hash::new();
+    az.update(seed);
+    let mut az = az.finalize();
+    az[0] &= 248;
+    az[31] &= 63;
+    az[31] |= 64;
+
     let mut hasher = sha512::Hash::new();
-    hasher.update(&signature[0..32]);
+    hasher.update(&az[32..64]);
+    hasher.update(message);
+    let mut nonce = hasher.finalize();
+    sc_reduce(&mut nonce);
+    let r = GeP3::scalarmult_base(&nonce[0..32]).to_bytes();
+
+    let mut hasher = sha512::Hash::new();
+    hasher.update(r);
     hasher.update(public_key);
     hasher.update(message);
-    let mut hash = hasher.finalize();
-    sc_reduce(&mut hash);
+    let mut hram = hasher.finalize();
+    sc_reduce(&mut hram);
+
+    let mut signature = [0u8; 64];
+    signature[0..32].copy_from_slice(&r);
+    sc_muladd(
+        &mut signature[32..64],
+        &hram[0..32],
+        &az[0..32],
+        &nonce[0..32],
+    );
+    wipe(&mut az);
+    wipe(&mut nonce);
+    signature
+}
+
+#[cfg(test)]
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_finalize_batch() {
+    let vectors = [
+        ("8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c", "", "778cda0634c021fae8b1a9fa655ba13230f6fcfc5c5d519afb0872ec9bf1d64241cc3eed8ad47270d86d30e762ad17677c6fb1797e35bca7eba30388257e020f"),
+        ("8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c", "batch", "fafd4cf07a2e76ed29b7a89020a98abc345b7471ae85040fb52987a812cf494ca704564df0935bf120ed6ea56017b7804169e5796c39c79e8be49f08039cb609"),
+        ("8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394", "", "13e31ee4dc6bb4aa0ab85103554ca4dff9e55d630f90c281e06e5ba55ddbbf852891e50a5712d36b3e3c465fd3ac14d4c28e764c74567f3741a3da948ae02f06"),
+        ("8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394", "batch", "41e604982997b9f42ac96417b23a04fb27aeafa2d24c4c95786f13035729d0961ef810f31f495a4e30edc2763d009293e639d703b073be8fb78fd86737379409"),
+        ("ed4928c628d1c2c6eae90338905995612959273a5c63f93636c14614ac8737d1", "", "917ad98fcddf5dda18f51a2f8158d7e225032a7c2926c533ae770d72576b3bcd7bc28c6b2d77499dc26fc460240ed7315fe5641ad9e8d9076a9bd5e2229ce702"),
+        ("ed4928c628d1c2c6eae90338905995612959273a5c63f93636c14614ac8737d1", "batch", "2d5335e3e2a56c206efd13c6200c5c49f22355fd353f3ed6eebf484e43b32b331a09896e8eaa29a8b5b558ff9fc68c4de682c40560abca1295d89e1e6ae16803"),
+    ];
+    // Spans several batches
+    let mut items: Vec<(Vec<u8>, Vec<u8>, Vec<u8>)> = (0..150)
+        .map(|i| {
+            let (public_key, message, signature) = vectors[i % vectors.len()];
+            (hex(public_key), message.as_bytes().to_vec(), hex(signature))
+        })
+        .collect();
+    items[3].2[40] ^= 1;
+    items[70].2[0] ^= 1;
+    items[100].1.push(0);
+    items[120].2[63] |= 0xf0;
+    items[149].0 = hex(vectors[0].0);
+    let invalid = [3, 70, 100, 120, 149];
+
+    let verifiers: Vec<Verifier> = items
+        .iter()
+        .map(|(public_key, message, signature)| {
+            let mut verifier = Verifier::new(public_key, signature);
+            verifier.update(message);
+            verifier
+        })
+        .collect();
+    let valid = Verifier::finalize_batch(&verifiers, ValidationMode::Classic);
+    assert_eq!(valid.len(), items.len());
+    for (i, (valid, verifier)) in valid.iter().zip(verifiers.iter()).enumerate() {
+        assert_eq!(*valid, !invalid.contains(&i));
+        assert_eq!(*valid, verifier.finalize(ValidationMode::Classic));
+    }
+    assert!(
+        Verifier::finalize_batch(&verifiers[4..64], ValidationMode::Classic)
+            .iter()
+            .all(|&valid| valid)
+    );
+    assert!(Verifier::finalize_batch(&[], ValidationMode::Classic).is_empty());
+}
 
-    let r = GeP2::double_scalarmult_vartime(hash.as_ref(), a, &signature[32..64]);
-    r.to_bytes()
-        .as_ref()
+#[test]
+fn test_validation_modes() {
+    // (description, public key, message, signature, valid in Classic, Strict
+    // and Zip215 modes)
+    let vectors = [
+        ("valid signature", "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c", "ValidationMode", "d0eb4217966a8f65b78f413347ef628adac5b99ffa4c6d53b54d2243a0d555701a449d52bb928d89c6602db58eb1210c3a68f8f8cea44bba2bc0d93c6f603500", [true, true, true]),
+        ("identity key and R, S = 0", "0100000000000000000000000000000000000000000000000000000000000000", "ValidationMode", "01000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000", [false, false, true]),
+        ("non-canonical identity key, S = 0", "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f", "ValidationMode", "01000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000", [true, false, true]),
+        ("non-canonical S", "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c", "ValidationMode", "d0eb4217966a8f65b78f413347ef628adac5b99ffa4c6d53b54d2243a0d55570071893afd5f59fe19cfd24586dab00213a68f8f8cea44bba2bc0d93c6f603510", [false, false, false]),
+        ("R with a small-order component", "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c", "ValidationMode", "feaf9d07b868cc52786305312c376563c622b0386a10d105e3c1501b28d65f7edc56b38eaeeaad348bffa3594fec8f80322d205b0be1d8cef28e845071ca460c", [false, false, true]),
+        ("non-canonical R", "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c", "ValidationMode", "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7fcbadd1fb05d8bb0ca71b62c6cefb3cfc03a58265190a9fcab92c453a5e7e390f", [false, false, true]),
+        ("small-order key, S = 0", "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f", "small order key 0", "01000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000", [true, false, true]),
+        ("all-zero key, S = 0", "0000000000000000000000000000000000000000000000000000000000000000", "ValidationMode", "01000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000", [false, false, true]),
+    ];
+    let modes = [
+        ValidationMode::Classic,
+        ValidationMode::Strict,
+        ValidationMode::Zip215,
+    ];
+    let verifiers: Vec<Verifier> = vectors
         .iter()
-        .zip(signature.iter())
-        .fold(0, |acc, (x, y)| acc | (x ^ y))
-        == 0
+        .map(|(_, public_key, message, signature, _)| {
+            let mut verifier = Verifier::new(&hex(public_key), &hex(signature));
+            verifier.update(message.as_bytes());
+            verifier
+        })
+        .collect();
+    for ((description, .., expected), verifier) in vectors.iter().zip(verifiers.iter()) {
+        for (mode, expected) in modes.iter().zip(expected.iter()) {
+            assert_eq!(
+                verifier.finalize(*mode),
+                *expected,
+                "{} in {:?} mode",
+                description,
+                mode
+            );
+            let key = PreparedKey::new(&verifier.public_key);
+            assert_eq!(verifier.finalize_prepared(&key, *mode), *expected);
+        }
+    }
+
+    // Batch verification gives the same results in every mode
+    for (m, mode) in modes.iter().enumerate() {
+        let valid = Verifier::finalize_batch(&verifiers, *mode);
+        for (valid, (description, .., expected)) in valid.iter().zip(vectors.iter()) {
+            assert_eq!(
+                *valid, expected[m],
+                "{} in a batch in {:?} mode",
+                description, mode
+            );
+        }
+    }
+}
+
+#[cfg(feature = "sign")]
+#[test]
+fn test_rfc8032_signatures() {
+    // RFC 8032, section 7.1, tests 1 to 3
+    let vectors = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        ),
+    ];
+    for (seed, public_key, message, expected) in vectors.iter() {
+        let mut seed_bytes = [0u8; 32];
+        seed_bytes.copy_from_slice(&hex(seed));
+        let (secret_key, pk) = keypair(&seed_bytes);
+        assert_eq!(&pk[..], &hex(public_key)[..]);
+        let message = hex(message);
+        let signature = signature(&message, &secret_key);
+        assert_eq!(&signature[..], &hex(expected)[..]);
+        let mut verifier = Verifier::new(&pk, &signature);
+        verifier.update(&message);
+        assert!(verifier.finalize(ValidationMode::Strict));
+    }
 }
@@ -1,6 +1,17 @@
-mod cryptoutil;
+#[cfg(any(
+    target_arch = "x86_64",
+    all(target_arch = "aarch64", target_feature = "neon")
+))]
+mod blake2b_simd;
+pub(crate) mod cryptoutil;
 mod curve25519;
-mod sha512;
+#[cfg(feature = "secret-key")]
+mod sha256;
+#[cfg(all(target_arch = "x86_64", feature = "sha512-x86"))]
+mod sha512_x86;
 
 pub mod blake2b;
 pub mod ed25519;
+#[cfg(feature = "secret-key")]
+pub mod scrypt;
+pub mod sha512;
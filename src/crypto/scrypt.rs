@@ -0,0 +1,213 @@
+//! scrypt, as used by libsodium's `crypto_pwhash_scryptsalsa208sha256()`
+
+use alloc::vec;
+
+use super::sha256;
+
+#[derive(Clone)]
+struct HmacSha256 {
+    inner: sha256::Hash,
+    outer: sha256::Hash,
+}
+
+impl HmacSha256 {
+    fn new(key: &[u8]) -> Self {
+        let mut k = [0u8; 64];
+        if key.len() > 64 {
+            let mut h = sha256::Hash::new();
+            h.update(key);
+            k[..32].copy_from_slice(&h.finalize());
+        } else {
+            k[..key.len()].copy_from_slice(key);
+        }
+        let mut inner = sha256::Hash::new();
+        let mut outer = sha256::Hash::new();
+        let mut padded = [0u8; 64];
+        for (p, k) in padded.iter_mut().zip(k.iter()) {
+            *p = k ^ 0x36;
+        }
+        inner.update(padded);
+        for (p, k) in padded.iter_mut().zip(k.iter()) {
+            *p = k ^ 0x5c;
+        }
+        outer.update(padded);
+        HmacSha256 { inner, outer }
+    }
+
+    fn update<T: AsRef<[u8]>>(&mut self, input: T) {
+        self.inner.update(input);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        let mut outer = self.outer;
+        outer.update(self.inner.finalize());
+        outer.finalize()
+    }
+}
+
+/// PBKDF2-HMAC-SHA256 with a single iteration, the only count scrypt uses
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], out: &mut [u8]) {
+    let mac = HmacSha256::new(password);
+    for (i, chunk) in out.chunks_mut(32).enumerate() {
+        let mut mac = mac.clone();
+        mac.update(salt);
+        mac.update(((i + 1) as u32).to_be_bytes());
+        let t = mac.finalize();
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+}
+
+fn salsa20_8(b: &mut [u32; 16]) {
+    let mut x = *b;
+    macro_rules! quarter(($a:expr, $b:expr, $c:expr, $d:expr) => {
+        x[$b] ^= x[$a].wrapping_add(x[$d]).rotate_left(7);
+        x[$c] ^= x[$b].wrapping_add(x[$a]).rotate_left(9);
+        x[$d] ^= x[$c].wrapping_add(x[$b]).rotate_left(13);
+        x[$a] ^= x[$d].wrapping_add(x[$c]).rotate_left(18);
+    });
+    for _ in 0..4 {
+        quarter!(0, 4, 8, 12);
+        quarter!(5, 9, 13, 1);
+        quarter!(10, 14, 2, 6);
+        quarter!(15, 3, 7, 11);
+        quarter!(0, 1, 2, 3);
+        quarter!(5, 6, 7, 4);
+        quarter!(10, 11, 8, 9);
+        quarter!(15, 12, 13, 14);
+    }
+    for (b, x) in b.iter_mut().zip(x.iter()) {
+        *b = b.wrapping_add(*x);
+    }
+}
+
+fn block_mix(b: &[u32], y: &mut [u32], r: usize) {
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&b[(2 * r - 1) * 16..]);
+    for i in 0..2 * r {
+        for (x, b) in x.iter_mut().zip(b[i * 16..(i + 1) * 16].iter()) {
+            *x ^= b;
+        }
+        salsa20_8(&mut x);
+        let pos = (i / 2 + (i & 1) * r) * 16;
+        y[pos..pos + 16].copy_from_slice(&x);
+    }
+}
+
+fn smix(b: &mut [u8], r: usize, n: usize, v: &mut [u32], xy: &mut [u32]) {
+    let words = 32 * r;
+    let (x, y) = xy.split_at_mut(words);
+    for (x, b) in x.iter_mut().zip(b.chunks(4)) {
+        *x = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    }
+    for i in 0..n {
+        v[i * words..(i + 1) * words].copy_from_slice(x);
+        block_mix(x, y, r);
+        x.copy_from_slice(y);
+    }
+    for _ in 0..n {
+        let j = (x[(2 * r - 1) * 16] as usize) & (n - 1);
+        for (x, v) in x.iter_mut().zip(v[j * words..(j + 1) * words].iter()) {
+            *x ^= v;
+        }
+        block_mix(x, y, r);
+        x.copy_from_slice(y);
+    }
+    for (b, x) in b.chunks_mut(4).zip(x.iter()) {
+        b.copy_from_slice(&x.to_le_bytes());
+    }
+}
+
+/// Compute scrypt(password, salt, 2^n_log2, r, p) into `out`
+///
+/// Returns `None` if the parameters would require an unreasonable amount of
+/// memory or computation.
+pub fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    n_log2: u32,
+    r: u32,
+    p: u32,
+    out: &mut [u8],
+) -> Option<()> {
+    if n_log2 == 0 || n_log2 >= usize::BITS - 1 || r == 0 || p == 0 {
+        return None;
+    }
+    let (n, r, p) = (1usize << n_log2, r as usize, p as usize);
+    if (r as u64) * (p as u64) >= 1 << 30 {
+        return None;
+    }
+    let block_len = 128usize.checked_mul(r)?;
+    let b_len = block_len.checked_mul(p)?;
+    let v_words = n.checked_mul(32)?.checked_mul(r)?;
+    if (v_words as u64).checked_mul(4)?.checked_add(b_len as u64)? > MAX_MEMORY {
+        return None;
+    }
+    if (n as u64)
+        .checked_mul(r as u64)?
+        .checked_mul(p as u64)?
+        .checked_mul(4)?
+        > MAX_OPS
+    {
+        return None;
+    }
+    let mut b = vec![0u8; b_len];
+    pbkdf2_sha256(password, salt, &mut b);
+    let mut v = vec![0u32; v_words];
+    let mut xy = vec![0u32; 64 * r];
+    for chunk in b.chunks_mut(block_len) {
+        smix(chunk, r, n, &mut v, &mut xy);
+    }
+    pbkdf2_sha256(password, &b, out);
+    Some(())
+}
+
+/// The largest amount of memory scrypt is allowed to use
+const MAX_MEMORY: u64 = 1 << 32;
+
+/// The largest amount of computation scrypt is allowed to perform, in
+/// libsodium's `opslimit` units (`4 * N * r * p`)
+const MAX_OPS: u64 = 1 << 36;
+
+/// Translate libsodium's `opslimit` and `memlimit` into scrypt parameters,
+/// returned as `(n_log2, r, p)`
+pub fn pick_params(opslimit: u64, memlimit: u64) -> (u32, u32, u32) {
+    let opslimit = opslimit.max(32768);
+    let r = 8u32;
+    let log2_above_half = |max_n: u64| {
+        let mut n_log2 = 1;
+        while n_log2 < 63 && (1u64 << n_log2) <= max_n / 2 {
+            n_log2 += 1;
+        }
+        n_log2
+    };
+    if opslimit < memlimit / 32 {
+        let n_log2 = log2_above_half(opslimit / (r as u64 * 4));
+        (n_log2, r, 1)
+    } else {
+        let n_log2 = log2_above_half(memlimit / (r as u64 * 128));
+        let max_rp = ((opslimit / 4) >> n_log2).min(0x3fff_ffff);
+        (n_log2, r, max_rp as u32 / r)
+    }
+}
+
+#[test]
+fn test_scrypt() {
+    let mut out = [0u8; 64];
+    scrypt(b"password", b"NaCl", 10, 8, 16, &mut out).unwrap();
+    assert_eq!(
+        out[..],
+        [
+            0xfd, 0xba, 0xbe, 0x1c, 0x9d, 0x34, 0x72, 0x00, 0x78, 0x56, 0xe7, 0x19, 0x0d, 0x01,
+            0xe9, 0xfe, 0x7c, 0x6a, 0xd7, 0xcb, 0xc8, 0x23, 0x78, 0x30, 0xe7, 0x73, 0x76, 0x63,
+            0x4b, 0x37, 0x31, 0x62, 0x2e, 0xaf, 0x30, 0xd9, 0x2e, 0x22, 0xa3, 0x88, 0x6f, 0xf1,
+            0x09, 0x27, 0x9d, 0x98, 0x30, 0xda, 0xc7, 0x27, 0xaf, 0xb9, 0x4a, 0x83, 0xee, 0x6d,
+            0x83, 0x60, 0xcb, 0xdf, 0xa2, 0xcc, 0x06, 0x40,
+        ][..]
+    );
+    assert_eq!(pick_params(33554432, 1073741824), (20, 8, 1));
+    assert_eq!(pick_params(32768, 1073741824), (10, 8, 1));
+
+    let (n_log2, r, p) = pick_params(1 << 40, 1 << 14);
+    assert!(p > 1 << 26);
+    assert!(scrypt(b"password", b"NaCl", n_log2, r, p, &mut out).is_none());
+}
@@ -0,0 +1,117 @@
+//! A small, self-contained SHA256 implementation, only used by scrypt
+
+#![allow(non_snake_case, clippy::unreadable_literal)]
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+#[derive(Copy, Clone)]
+pub struct Hash {
+    state: [u32; 8],
+    w: [u8; 64],
+    r: usize,
+    len: u64,
+}
+
+fn compress(state: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (i, e) in w.iter_mut().take(16).enumerate() {
+        let mut x = [0u8; 4];
+        x.copy_from_slice(&block[i * 4..i * 4 + 4]);
+        *e = u32::from_be_bytes(x);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+    let mut t = *state;
+    for (k, w) in ROUND_CONSTANTS.iter().zip(w.iter()) {
+        let S1 = t[4].rotate_right(6) ^ t[4].rotate_right(11) ^ t[4].rotate_right(25);
+        let ch = (t[4] & t[5]) ^ (!t[4] & t[6]);
+        let t1 = t[7]
+            .wrapping_add(S1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*w);
+        let S0 = t[0].rotate_right(2) ^ t[0].rotate_right(13) ^ t[0].rotate_right(22);
+        let maj = (t[0] & t[1]) ^ (t[0] & t[2]) ^ (t[1] & t[2]);
+        let t2 = S0.wrapping_add(maj);
+        t = [
+            t1.wrapping_add(t2),
+            t[0],
+            t[1],
+            t[2],
+            t[3].wrapping_add(t1),
+            t[4],
+            t[5],
+            t[6],
+        ];
+    }
+    for (s, t) in state.iter_mut().zip(t.iter()) {
+        *s = s.wrapping_add(*t);
+    }
+}
+
+impl Hash {
+    pub fn new() -> Hash {
+        Hash {
+            state: IV,
+            w: [0u8; 64],
+            r: 0,
+            len: 0,
+        }
+    }
+
+    /// Absorb content
+    pub fn update<T: AsRef<[u8]>>(&mut self, input: T) {
+        let mut input = input.as_ref();
+        self.len += input.len() as u64;
+        while !input.is_empty() {
+            let tc = ::core::cmp::min(input.len(), 64 - self.r);
+            self.w[self.r..self.r + tc].copy_from_slice(&input[..tc]);
+            self.r += tc;
+            input = &input[tc..];
+            if self.r == 64 {
+                compress(&mut self.state, &self.w);
+                self.r = 0;
+            }
+        }
+    }
+
+    /// Compute SHA256(absorbed content)
+    pub fn finalize(mut self) -> [u8; 32] {
+        let bits = self.len * 8;
+        self.update([0x80]);
+        while self.r != 56 {
+            self.update([0x00]);
+        }
+        self.update(bits.to_be_bytes());
+        let mut out = [0u8; 32];
+        for (o, s) in out.chunks_mut(4).zip(self.state.iter()) {
+            o.copy_from_slice(&s.to_be_bytes());
+        }
+        out
+    }
+}
+
+impl Default for Hash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -10,6 +10,9 @@
     clippy::unreadable_literal
 )]
 
+#[cfg(all(target_arch = "x86_64", feature = "sha512-x86"))]
+use super::sha512_x86;
+
 #[inline(always)]
 fn load_be(base: &[u8], offset: usize) -> u64 {
     let addr = &base[offset..];
@@ -36,6 +39,89 @@ fn store_be(base: &mut [u8], offset: usize, x: u64) {
     addr[0] = (x >> 56) as u8;
 }
 
+pub(super) static ROUND_CONSTANTS: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
 struct W([u64; 16]);
 
 #[derive(Copy, Clone)]
@@ -132,88 +218,6 @@ impl W {
     }
 
     fn G(&mut self, state: &mut State, s: usize) {
-        const ROUND_CONSTANTS: [u64; 80] = [
-            0x428a2f98d728ae22,
-            0x7137449123ef65cd,
-            0xb5c0fbcfec4d3b2f,
-            0xe9b5dba58189dbbc,
-            0x3956c25bf348b538,
-            0x59f111f1b605d019,
-            0x923f82a4af194f9b,
-            0xab1c5ed5da6d8118,
-            0xd807aa98a3030242,
-            0x12835b0145706fbe,
-            0x243185be4ee4b28c,
-            0x550c7dc3d5ffb4e2,
-            0x72be5d74f27b896f,
-            0x80deb1fe3b1696b1,
-            0x9bdc06a725c71235,
-            0xc19bf174cf692694,
-            0xe49b69c19ef14ad2,
-            0xefbe4786384f25e3,
-            0x0fc19dc68b8cd5b5,
-            0x240ca1cc77ac9c65,
-            0x2de92c6f592b0275,
-            0x4a7484aa6ea6e483,
-            0x5cb0a9dcbd41fbd4,
-            0x76f988da831153b5,
-            0x983e5152ee66dfab,
-            0xa831c66d2db43210,
-            0xb00327c898fb213f,
-            0xbf597fc7beef0ee4,
-            0xc6e00bf33da88fc2,
-            0xd5a79147930aa725,
-            0x06ca6351e003826f,
-            0x142929670a0e6e70,
-            0x27b70a8546d22ffc,
-            0x2e1b21385c26c926,
-            0x4d2c6dfc5ac42aed,
-            0x53380d139d95b3df,
-            0x650a73548baf63de,
-            0x766a0abb3c77b2a8,
-            0x81c2c92e47edaee6,
-            0x92722c851482353b,
-            0xa2bfe8a14cf10364,
-            0xa81a664bbc423001,
-            0xc24b8b70d0f89791,
-            0xc76c51a30654be30,
-            0xd192e819d6ef5218,
-            0xd69906245565a910,
-            0xf40e35855771202a,
-            0x106aa07032bbd1b8,
-            0x19a4c116b8d2d0c8,
-            0x1e376c085141ab53,
-            0x2748774cdf8eeb99,
-            0x34b0bcb5e19b48a8,
-            0x391c0cb3c5c95a63,
-            0x4ed8aa4ae3418acb,
-            0x5b9cca4f7763e373,
-            0x682e6ff3d6b2b8a3,
-            0x748f82ee5defb2fc,
-            0x78a5636f43172f60,
-            0x84c87814a1f0ab72,
-            0x8cc702081a6439ec,
-            0x90befffa23631e28,
-            0xa4506cebde82bde9,
-            0xbef9a3f7b2c67915,
-            0xc67178f2e372532b,
-            0xca273eceea26619c,
-            0xd186b8c721c0c207,
-            0xeada7dd6cde0eb1e,
-            0xf57d4f7fee6ed178,
-            0x06f067aa72176fba,
-            0x0a637dc5a2c898a6,
-            0x113f9804bef90dae,
-            0x1b710b35131c471b,
-            0x28db77f523047d84,
-            0x32caab7b40c72493,
-            0x3c9ebe0a15c9bebc,
-            0x431d67c49c100d4c,
-            0x4cc5d4becb3e42b6,
-            0x597f299cfc657e2a,
-            0x5fcb6fab3ad6faec,
-            0x6c44198c4a475817,
-        ];
         let rc = &ROUND_CONSTANTS[s * 16..];
         self.F(state, 0, rc[0]);
         self.F(state, 1, rc[1]);
@@ -250,6 +254,20 @@ impl State {
         State(t)
     }
 
+    /// The initial state of SHA-512/256
+    fn new_512_256() -> Self {
+        State([
+            0x22312194fc2bf72c,
+            0x9f555fa3c84c64c2,
+            0x2393b86b6f53b151,
+            0x963877195940eabd,
+            0x96283ee2a88effe3,
+            0xbe5e1e2553863992,
+            0x2b0199fc2c85b8aa,
+            0x0eb72ddc81c52ca2,
+        ])
+    }
+
     #[inline(always)]
     fn add(&mut self, x: &State) {
         let sx = &mut self.0;
@@ -293,12 +311,146 @@ impl State {
     }
 }
 
+/// One round, with the working variables named in their current roles
+///
+/// Rounds from 16 on first extend the message schedule in place.
+#[cfg(target_pointer_width = "64")]
+macro_rules! round {
+    ($w:ident, $a:ident, $b:ident, $c:ident, $d:ident, $e:ident, $f:ident, $g:ident, $h:ident, $i:expr) => {
+        // W[i - 2], W[i - 7] and W[i - 15], as indices modulo 16
+        if $i >= 16 {
+            $w[$i & 15] = $w[$i & 15]
+                .wrapping_add(W::sigma1($w[($i + 14) & 15]))
+                .wrapping_add($w[($i + 9) & 15])
+                .wrapping_add(W::sigma0($w[($i + 1) & 15]));
+        }
+        let t1 = $h
+            .wrapping_add(W::Sigma1($e))
+            .wrapping_add(W::Ch($e, $f, $g))
+            .wrapping_add(ROUND_CONSTANTS[$i])
+            .wrapping_add($w[$i & 15]);
+        $d = $d.wrapping_add(t1);
+        $h = t1
+            .wrapping_add(W::Sigma0($a))
+            .wrapping_add(W::Maj($a, $b, $c));
+    };
+}
+
+/// Eight rounds, after which the working variables are back in place
+#[cfg(target_pointer_width = "64")]
+macro_rules! rounds8 {
+    ($w:ident, $a:ident, $b:ident, $c:ident, $d:ident, $e:ident, $f:ident, $g:ident, $h:ident, $i:expr) => {
+        round!($w, $a, $b, $c, $d, $e, $f, $g, $h, $i);
+        round!($w, $h, $a, $b, $c, $d, $e, $f, $g, $i + 1);
+        round!($w, $g, $h, $a, $b, $c, $d, $e, $f, $i + 2);
+        round!($w, $f, $g, $h, $a, $b, $c, $d, $e, $i + 3);
+        round!($w, $e, $f, $g, $h, $a, $b, $c, $d, $i + 4);
+        round!($w, $d, $e, $f, $g, $h, $a, $b, $c, $i + 5);
+        round!($w, $c, $d, $e, $f, $g, $h, $a, $b, $i + 6);
+        round!($w, $b, $c, $d, $e, $f, $g, $h, $a, $i + 7);
+    };
+}
+
+/// Process all the complete blocks of `input`, with the 80 rounds fully
+/// unrolled and the working variables kept in registers
+///
+/// Returns the number of remaining bytes.
+#[cfg(target_pointer_width = "64")]
+fn blocks_unrolled(state: &mut State, mut input: &[u8]) -> usize {
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state.0;
+    while input.len() >= 128 {
+        let mut w = W::new(input).0;
+        rounds8!(w, a, b, c, d, e, f, g, h, 0);
+        rounds8!(w, a, b, c, d, e, f, g, h, 8);
+        rounds8!(w, a, b, c, d, e, f, g, h, 16);
+        rounds8!(w, a, b, c, d, e, f, g, h, 24);
+        rounds8!(w, a, b, c, d, e, f, g, h, 32);
+        rounds8!(w, a, b, c, d, e, f, g, h, 40);
+        rounds8!(w, a, b, c, d, e, f, g, h, 48);
+        rounds8!(w, a, b, c, d, e, f, g, h, 56);
+        rounds8!(w, a, b, c, d, e, f, g, h, 64);
+        rounds8!(w, a, b, c, d, e, f, g, h, 72);
+        state.add(&State([a, b, c, d, e, f, g, h]));
+        [a, b, c, d, e, f, g, h] = state.0;
+        input = &input[128..];
+    }
+    input.len()
+}
+
+/// An implementation of the compression function
+///
+/// All implementations compute exactly the same function. The fastest one
+/// supported by the CPU is selected when a hash is created.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    /// The original implementation, which is also the smallest
+    Portable,
+    /// Fully unrolled rounds, on 64-bit targets
+    #[cfg(target_pointer_width = "64")]
+    Unrolled,
+    /// The SHA512 instructions of x86_64 CPUs, with the `sha512-x86` feature
+    #[cfg(all(target_arch = "x86_64", feature = "sha512-x86"))]
+    Sha512,
+}
+
+impl Backend {
+    /// Return the fastest implementation supported by the current CPU
+    ///
+    /// With the `std` feature, x86_64 CPUs are probed at runtime. Otherwise,
+    /// only the features enabled at compile time are used.
+    pub fn detect() -> Backend {
+        #[cfg(all(target_arch = "x86_64", feature = "sha512-x86"))]
+        {
+            if Backend::Sha512.is_supported() {
+                return Backend::Sha512;
+            }
+        }
+        #[cfg(target_pointer_width = "64")]
+        {
+            return Backend::Unrolled;
+        }
+        #[allow(unreachable_code)]
+        Backend::Portable
+    }
+
+    /// Return `true` if the current CPU supports this implementation
+    pub fn is_supported(self) -> bool {
+        match self {
+            Backend::Portable => true,
+            #[cfg(target_pointer_width = "64")]
+            Backend::Unrolled => true,
+            #[cfg(all(target_arch = "x86_64", feature = "sha512-x86", feature = "std"))]
+            Backend::Sha512 => {
+                std::is_x86_feature_detected!("sha512") && std::is_x86_feature_detected!("avx2")
+            }
+            #[cfg(all(target_arch = "x86_64", feature = "sha512-x86", not(feature = "std")))]
+            Backend::Sha512 => cfg!(all(target_feature = "sha512", target_feature = "avx2")),
+        }
+    }
+
+    /// Process all the complete blocks of `input`
+    ///
+    /// Returns the number of remaining bytes. The implementation must be
+    /// supported by the current CPU.
+    fn blocks(self, state: &mut State, input: &[u8]) -> usize {
+        debug_assert!(self.is_supported());
+        match self {
+            Backend::Portable => state.blocks(input),
+            #[cfg(target_pointer_width = "64")]
+            Backend::Unrolled => blocks_unrolled(state, input),
+            #[cfg(all(target_arch = "x86_64", feature = "sha512-x86"))]
+            Backend::Sha512 => unsafe { sha512_x86::blocks(&mut state.0, input) },
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct Hash {
     state: State,
     w: [u8; 128],
     r: usize,
-    len: usize,
+    len: u64,
+    backend: Backend,
 }
 
 impl Hash {
@@ -308,14 +460,28 @@ impl Hash {
             r: 0,
             w: [0u8; 128],
             len: 0,
+            backend: Backend::detect(),
+        }
+    }
+
+    /// Use `backend` for the compression function, instead of the fastest
+    /// one supported by the CPU
+    ///
+    /// Returns `None` if the CPU doesn't support it.
+    #[cfg(test)]
+    pub fn with_backend(mut self, backend: Backend) -> Option<Hash> {
+        if !backend.is_supported() {
+            return None;
         }
+        self.backend = backend;
+        Some(self)
     }
 
     /// Absorb content
     pub fn update<T: AsRef<[u8]>>(&mut self, input: T) {
         let input = input.as_ref();
         let mut n = input.len();
-        self.len += n;
+        self.len += n as u64;
         let av = 128 - self.r;
         let tc = ::core::cmp::min(n, av);
         self.w[self.r..self.r + tc].copy_from_slice(&input[0..tc]);
@@ -323,11 +489,11 @@ impl Hash {
         n -= tc;
         let pos = tc;
         if self.r == 128 {
-            self.state.blocks(&self.w);
+            self.backend.blocks(&mut self.state, &self.w);
             self.r = 0;
         }
         if self.r == 0 && n > 0 {
-            let rb = self.state.blocks(&input[pos..]);
+            let rb = self.backend.blocks(&mut self.state, &input[pos..]);
             if rb > 0 {
                 self.w[..rb].copy_from_slice(&input[pos + n - rb..]);
                 self.r = rb;
@@ -341,11 +507,11 @@ impl Hash {
         padded[..self.r].copy_from_slice(&self.w[..self.r]);
         padded[self.r] = 0x80;
         let r = if self.r < 112 { 128 } else { 256 };
-        let bits = self.len * 8;
+        let bits = self.len.wrapping_mul(8);
         for i in 0..8 {
-            padded[r - 8 + i] = (bits as u64 >> (56 - i * 8)) as u8;
+            padded[r - 8 + i] = (bits >> (56 - i * 8)) as u8;
         }
-        self.state.blocks(&padded[..r]);
+        self.backend.blocks(&mut self.state, &padded[..r]);
         let mut out = [0u8; 64];
         self.state.store(&mut out);
         out
@@ -357,3 +523,148 @@ impl Default for Hash {
         Self::new()
     }
 }
+
+/// SHA-512/256: SHA-512 with a different initial state, truncated to 256
+/// bits
+#[derive(Copy, Clone)]
+pub struct Hash512_256(Hash);
+
+impl Hash512_256 {
+    pub fn new() -> Hash512_256 {
+        let mut hash = Hash::new();
+        hash.state = State::new_512_256();
+        Hash512_256(hash)
+    }
+
+    /// Absorb content
+    pub fn update<T: AsRef<[u8]>>(&mut self, input: T) {
+        self.0.update(input)
+    }
+
+    /// Compute SHA512/256(absorbed content)
+    pub fn finalize(self) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&self.0.finalize()[..32]);
+        out
+    }
+}
+
+impl Default for Hash512_256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Return the implementations supported by the current CPU
+#[cfg(test)]
+pub fn backends() -> Vec<Backend> {
+    let all = [
+        Backend::Portable,
+        #[cfg(target_pointer_width = "64")]
+        Backend::Unrolled,
+        #[cfg(all(target_arch = "x86_64", feature = "sha512-x86"))]
+        Backend::Sha512,
+    ];
+    all.iter().copied().filter(|b| b.is_supported()).collect()
+}
+
+#[cfg(test)]
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_nist_vectors() {
+    // FIPS 180-2, appendix C, and the NIST CSRC examples
+    let vectors: [(&[u8], &str); 3] = [
+        (
+            b"",
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+             47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e",
+        ),
+        (
+            b"abc",
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        ),
+        (
+            b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+              hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+            "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018\
+             501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909",
+        ),
+    ];
+    for backend in backends() {
+        for (input, expected) in vectors.iter() {
+            let mut hasher = Hash::new().with_backend(backend).unwrap();
+            hasher.update(input);
+            assert_eq!(hex(&hasher.finalize()), *expected, "{:?}", backend);
+        }
+
+        // One million repetitions of "a"
+        let mut hasher = Hash::new().with_backend(backend).unwrap();
+        for _ in 0..1000 {
+            hasher.update([b'a'; 1000]);
+        }
+        assert_eq!(
+            hex(&hasher.finalize()),
+            "e718483d0ce769644e2e42c7bc15b4638e1f98b13b2044285632a803afa973eb\
+             de0ff244877ea60a4cb0432ce577c31beb009c5c2c49aa2e4eadb217ad8cc09b",
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn test_sha512_256() {
+    // FIPS 180-4 examples
+    let vectors: [(&[u8], &str); 3] = [
+        (
+            b"",
+            "c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a",
+        ),
+        (
+            b"abc",
+            "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+        ),
+        (
+            b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+              hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+            "3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a",
+        ),
+    ];
+    for (input, expected) in vectors.iter() {
+        let mut hasher = Hash512_256::new();
+        hasher.update(input);
+        assert_eq!(hex(&hasher.finalize()), *expected);
+    }
+}
+
+#[test]
+fn test_backends() {
+    assert!(backends().contains(&Backend::detect()));
+
+    // Hash messages of every length around the block boundaries, in one
+    // call and in uneven chunks
+    let input: Vec<u8> = (0..1031u32).map(|i| (i * 7 + i / 256) as u8).collect();
+    for len in (0..=600).chain(1000..=input.len()) {
+        let input = &input[..len];
+        let mut hasher = Hash::new().with_backend(Backend::Portable).unwrap();
+        hasher.update(input);
+        let expected = hasher.finalize();
+        for backend in backends() {
+            let mut hasher = Hash::new().with_backend(backend).unwrap();
+            for chunk in input.chunks(len % 131 + 1) {
+                hasher.update(chunk);
+            }
+            assert_eq!(
+                hasher.finalize()[..],
+                expected[..],
+                "{:?}, {} bytes",
+                backend,
+                len
+            );
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! SHA512 compression function using the SHA512 instructions of x86_64
+//! CPUs
+//!
+//! The state is kept as two vectors, `ABEF` and `CDGH`, which is the layout
+//! `vsha512rnds2` expects. Each instruction computes two rounds, and returns
+//! the new `ABEF` while the previous `ABEF` becomes the new `CDGH`. The
+//! message schedule is computed four words at a time, with `vsha512msg1` and
+//! `vsha512msg2`.
+
+use core::arch::x86_64::*;
+
+use super::sha512::ROUND_CONSTANTS;
+
+/// Swap the bytes of each 64-bit lane
+static BSWAP64: [u8; 32] = [
+    7, 6, 5, 4, 3, 2, 1, 0, 15, 14, 13, 12, 11, 10, 9, 8, //
+    7, 6, 5, 4, 3, 2, 1, 0, 15, 14, 13, 12, 11, 10, 9, 8,
+];
+
+/// Return the next four message words, `W[t..t + 4]`, from the previous
+/// sixteen
+#[inline(always)]
+unsafe fn schedule(w0: __m256i, w4: __m256i, w8: __m256i, w12: __m256i) -> __m256i {
+    // W[t - 16] + sigma0(W[t - 15])
+    let t = _mm256_sha512msg1_epi64(w0, _mm256_castsi256_si128(w4));
+    // + W[t - 7], which spans w8 and w12
+    let w9 = _mm256_permute4x64_epi64(_mm256_blend_epi32(w8, w12, 0x03), 0b00_11_10_01);
+    // + sigma1(W[t - 2]), which depends on the words being computed
+    _mm256_sha512msg2_epi64(_mm256_add_epi64(t, w9), w12)
+}
+
+/// Four rounds, with the message words `w` and the round constants from `i`
+#[inline(always)]
+unsafe fn rounds4(abef: &mut __m256i, cdgh: &mut __m256i, w: __m256i, i: usize) {
+    let k = _mm256_loadu_si256(ROUND_CONSTANTS[i..i + 4].as_ptr() as *const __m256i);
+    let wk = _mm256_add_epi64(w, k);
+    *cdgh = _mm256_sha512rnds2_epi64(*cdgh, *abef, _mm256_castsi256_si128(wk));
+    *abef = _mm256_sha512rnds2_epi64(*abef, *cdgh, _mm256_extracti128_si256(wk, 1));
+}
+
+/// Process all the complete blocks of `input`
+///
+/// Returns the number of remaining bytes.
+///
+/// # Safety
+///
+/// The CPU must support the SHA512 and AVX2 instructions.
+#[target_feature(enable = "sha512,avx2")]
+pub unsafe fn blocks(state: &mut [u64; 8], mut input: &[u8]) -> usize {
+    let bswap = _mm256_loadu_si256(BSWAP64.as_ptr() as *const __m256i);
+
+    // [A, B, C, D] and [E, F, G, H] to [F, E, B, A] and [H, G, D, C]
+    let dcba = _mm256_permute4x64_epi64(
+        _mm256_loadu_si256(state[0..4].as_ptr() as *const __m256i),
+        0b00_01_10_11,
+    );
+    let hgfe = _mm256_permute4x64_epi64(
+        _mm256_loadu_si256(state[4..8].as_ptr() as *const __m256i),
+        0b00_01_10_11,
+    );
+    let mut abef = _mm256_permute2x128_si256(hgfe, dcba, 0x31);
+    let mut cdgh = _mm256_permute2x128_si256(hgfe, dcba, 0x20);
+
+    while input.len() >= 128 {
+        let (abef0, cdgh0) = (abef, cdgh);
+        let p = input.as_ptr() as *const __m256i;
+        let mut w0 = _mm256_shuffle_epi8(_mm256_loadu_si256(p), bswap);
+        let mut w1 = _mm256_shuffle_epi8(_mm256_loadu_si256(p.add(1)), bswap);
+        let mut w2 = _mm256_shuffle_epi8(_mm256_loadu_si256(p.add(2)), bswap);
+        let mut w3 = _mm256_shuffle_epi8(_mm256_loadu_si256(p.add(3)), bswap);
+        rounds4(&mut abef, &mut cdgh, w0, 0);
+        rounds4(&mut abef, &mut cdgh, w1, 4);
+        rounds4(&mut abef, &mut cdgh, w2, 8);
+        rounds4(&mut abef, &mut cdgh, w3, 12);
+        for i in (16..80).step_by(16) {
+            w0 = schedule(w0, w1, w2, w3);
+            rounds4(&mut abef, &mut cdgh, w0, i);
+            w1 = schedule(w1, w2, w3, w0);
+            rounds4(&mut abef, &mut cdgh, w1, i + 4);
+            w2 = schedule(w2, w3, w0, w1);
+            rounds4(&mut abef, &mut cdgh, w2, i + 8);
+            w3 = schedule(w3, w0, w1, w2);
+            rounds4(&mut abef, &mut cdgh, w3, i + 12);
+        }
+        abef = _mm256_add_epi64(abef, abef0);
+        cdgh = _mm256_add_epi64(cdgh, cdgh0);
+        input = &input[128..];
+    }
+
+    let dcba = _mm256_permute2x128_si256(cdgh, abef, 0x31);
+    let hgfe = _mm256_permute2x128_si256(cdgh, abef, 0x20);
+    _mm256_storeu_si256(
+        state[0..4].as_mut_ptr() as *mut __m256i,
+        _mm256_permute4x64_epi64(dcba, 0b00_01_10_11),
+    );
+    _mm256_storeu_si256(
+        state[4..8].as_mut_ptr() as *mut __m256i,
+        _mm256_permute4x64_epi64(hgfe, 0b00_01_10_11),
+    );
+    input.len()
+}
@@ -0,0 +1,322 @@
+use alloc::collections::{btree_map, BTreeMap};
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::num::NonZeroUsize;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::{
+    prehash, Error, PublicKey, Signature, StreamVerifier, VerificationPolicy, DEFAULT_POLICY,
+};
+
+/// The outcome of a successful threshold verification
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ThresholdReport {
+    signers: Vec<[u8; 8]>,
+    invalid: Vec<usize>,
+}
+
+impl ThresholdReport {
+    /// Return the IDs of the keys that produced a valid signature, in the
+    /// order the signatures were provided
+    pub fn signers(&self) -> &[[u8; 8]] {
+        &self.signers
+    }
+
+    /// Return the indices of the signatures that were not counted, either
+    /// because they didn't verify or because their key is not in the key ring
+    ///
+    /// Duplicate signatures from a key that already signed are not listed.
+    pub fn invalid_signatures(&self) -> &[usize] {
+        &self.invalid
+    }
+}
+
+/// A collection of public keys, indexed by key ID
+///
+/// A key ring can verify signatures made by any of the keys it contains,
+/// which is useful when signing keys are rotated and signatures from
+/// multiple keys have to be accepted at the same time.
+///
+/// The key to use is selected according to the key ID of the signature.
+/// Adding a key whose ID is already present replaces the previous key.
+///
+/// ```rust
+/// use minisign_verify::{KeyRing, PublicKey, Signature};
+///
+/// let keyring = KeyRing::decode(
+///     "untrusted comment: minisign public key E7620F1842B4E81F
+/// RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+/// untrusted comment: minisign public key EFCDAB8967452301
+/// RWQBI0VniavN7wABAgMEBQYHCAkKCwwNDg8QERITFBUWFxgZGhscHR4f",
+/// )
+/// .expect("Unable to decode the key ring");
+///
+/// let signature = Signature::decode(
+///     "untrusted comment: signature from minisign secret key
+/// RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/\
+///      z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+/// trusted comment: timestamp:1633700835\tfile:test\tprehashed
+/// wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==",
+/// )
+/// .expect("Unable to decode the signature");
+///
+/// let public_key = keyring
+///     .verify(b"test", &signature, false)
+///     .expect("Signature didn't verify");
+/// assert_eq!(public_key.key_id(), signature.key_id());
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeyRing {
+    keys: BTreeMap<[u8; 8], PublicKey>,
+}
+
+impl KeyRing {
+    /// Create an empty key ring
+    pub fn new() -> Self {
+        KeyRing {
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Add a public key, returning the key previously stored with the same
+    /// key ID, if there was one
+    pub fn insert(&mut self, public_key: PublicKey) -> Option<PublicKey> {
+        self.keys.insert(public_key.key_id, public_key)
+    }
+
+    /// Remove the key with the given key ID
+    pub fn remove(&mut self, key_id: &[u8; 8]) -> Option<PublicKey> {
+        self.keys.remove(key_id)
+    }
+
+    /// Return the key with the given key ID
+    pub fn get(&self, key_id: &[u8; 8]) -> Option<&PublicKey> {
+        self.keys.get(key_id)
+    }
+
+    /// Return the number of keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Return `true` if the key ring doesn't contain any keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Return an iterator over the keys, ordered by key ID
+    pub fn iter(&self) -> btree_map::Values<'_, [u8; 8], PublicKey> {
+        self.keys.values()
+    }
+
+    /// Create a key ring from a string containing one or more public keys
+    ///
+    /// The string is a concatenation of `minisign.pub` files: each key is a
+    /// base64 line, optionally preceded by its untrusted comment. Empty
+    /// lines are ignored.
+    pub fn decode(lines_str: &str) -> Result<Self, Error> {
+        let mut keyring = KeyRing::new();
+        let mut untrusted_comment = None;
+        for line in lines_str.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("untrusted comment:") {
+                if untrusted_comment.is_some() {
+                    return Err(Error::InvalidEncoding);
+                }
+                untrusted_comment = Some(line);
+                continue;
+            }
+            let mut public_key = PublicKey::from_base64(line)?;
+            public_key.untrusted_comment = untrusted_comment.take().map(|c| c.to_string());
+            keyring.insert(public_key);
+        }
+        if untrusted_comment.is_some() {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(keyring)
+    }
+
+    /// Load a key ring from a file containing one or more public keys
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let bin = fs::read_to_string(path)?;
+        KeyRing::decode(&bin)
+    }
+
+    /// Load all the `.pub` files of a directory
+    ///
+    /// Each file may contain one or more public keys. Files are loaded in
+    /// name order, and subdirectories are ignored.
+    #[cfg(feature = "std")]
+    pub fn from_dir<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut paths = vec![];
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension() == Some("pub".as_ref()) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        let mut keyring = KeyRing::new();
+        for path in paths {
+            keyring.extend(KeyRing::from_file(path)?.keys.into_values());
+        }
+        Ok(keyring)
+    }
+
+    fn key_for(&self, signature: &Signature) -> Result<&PublicKey, Error> {
+        self.keys
+            .get(&signature.key_id)
+            .ok_or(Error::UnexpectedKeyId)
+    }
+
+    /// Verify that `signature` is a valid signature for `bin` using the key
+    /// with the signature's key ID
+    ///
+    /// Returns the key that verified the signature.
+    pub fn verify(
+        &self,
+        bin: &[u8],
+        signature: &Signature,
+        allow_legacy: bool,
+    ) -> Result<&PublicKey, Error> {
+        self.verify_with_policy(bin, signature, allow_legacy, &DEFAULT_POLICY)
+    }
+
+    /// Verify that `signature` is a valid signature for `bin` using the key
+    /// with the signature's key ID, and that it satisfies `policy`
+    ///
+    /// Returns the key that verified the signature.
+    pub fn verify_with_policy(
+        &self,
+        bin: &[u8],
+        signature: &Signature,
+        allow_legacy: bool,
+        policy: &VerificationPolicy,
+    ) -> Result<&PublicKey, Error> {
+        let public_key = self.key_for(signature)?;
+        public_key.verify_with_policy(bin, signature, allow_legacy, policy)?;
+        Ok(public_key)
+    }
+
+    /// Sets up a stream verifier using the key with the signature's key ID
+    ///
+    /// The selected key can be retrieved with `StreamVerifier::public_key()`.
+    pub fn verify_stream<'a>(
+        &'a self,
+        signature: &'a Signature,
+    ) -> Result<StreamVerifier<'a>, Error> {
+        self.key_for(signature)?.verify_stream(signature)
+    }
+
+    /// Sets up a stream verifier using the key with the signature's key ID,
+    /// that will also check the signature against `policy`
+    pub fn verify_stream_with_policy<'a>(
+        &'a self,
+        signature: &'a Signature,
+        policy: &'a VerificationPolicy,
+    ) -> Result<StreamVerifier<'a>, Error> {
+        self.key_for(signature)?
+            .verify_stream_with_policy(signature, policy)
+    }
+}
+
+impl KeyRing {
+    /// Verify that `bin` was signed by at least `threshold` distinct keys of
+    /// the key ring
+    ///
+    /// Each signature is checked against the key with its key ID, and every
+    /// key is only counted once, no matter how many signatures it produced.
+    /// The content is only hashed once for all the pre-hashed signatures.
+    ///
+    /// Returns a report listing the keys that signed, or
+    /// `Error::InsufficientSignatures` if the threshold wasn't met.
+    pub fn verify_threshold(
+        &self,
+        bin: &[u8],
+        signatures: &[Signature],
+        threshold: NonZeroUsize,
+        allow_legacy: bool,
+    ) -> Result<ThresholdReport, Error> {
+        self.verify_threshold_with_policy(bin, signatures, threshold, allow_legacy, &DEFAULT_POLICY)
+    }
+
+    /// Verify that `bin` was signed by at least `threshold` distinct keys of
+    /// the key ring, only counting signatures that also satisfy `policy`
+    pub fn verify_threshold_with_policy(
+        &self,
+        bin: &[u8],
+        signatures: &[Signature],
+        threshold: NonZeroUsize,
+        allow_legacy: bool,
+        policy: &VerificationPolicy,
+    ) -> Result<ThresholdReport, Error> {
+        let mut prehashed = None;
+        let mut signers: Vec<[u8; 8]> = vec![];
+        let mut invalid = vec![];
+        for (i, signature) in signatures.iter().enumerate() {
+            if signers.contains(&signature.key_id) {
+                continue;
+            }
+            let public_key = match self.keys.get(&signature.key_id) {
+                Some(public_key) => public_key,
+                None => {
+                    invalid.push(i);
+                    continue;
+                }
+            };
+            let signed: &[u8] = if signature.is_prehashed {
+                prehashed.get_or_insert_with(|| prehash(bin))
+            } else if allow_legacy {
+                bin
+            } else {
+                invalid.push(i);
+                continue;
+            };
+            match public_key.verify_ed25519(signed, signature, policy) {
+                Ok(()) => signers.push(signature.key_id),
+                Err(_) => invalid.push(i),
+            }
+        }
+        if signers.len() < threshold.get() {
+            return Err(Error::InsufficientSignatures {
+                valid: signers.len(),
+                required: threshold.get(),
+            });
+        }
+        Ok(ThresholdReport { signers, invalid })
+    }
+}
+
+impl Extend<PublicKey> for KeyRing {
+    fn extend<I: IntoIterator<Item = PublicKey>>(&mut self, iter: I) {
+        for public_key in iter {
+            self.insert(public_key);
+        }
+    }
+}
+
+impl FromIterator<PublicKey> for KeyRing {
+    fn from_iter<I: IntoIterator<Item = PublicKey>>(iter: I) -> Self {
+        let mut keyring = KeyRing::new();
+        keyring.extend(iter);
+        keyring
+    }
+}
+
+impl<'a> IntoIterator for &'a KeyRing {
+    type Item = &'a PublicKey;
+    type IntoIter = btree_map::Values<'a, [u8; 8], PublicKey>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -7,9 +7,23 @@
 //! ## Features
 //!
 //! * Verify signatures for both standard and pre-hashed modes
-//! * Streaming verification for large files
+//! * Streaming verification for large files, with SIMD-accelerated hashing
+//!   (AVX2, SSE4.1, NEON) selected at runtime
+//! * Fast SHA-512 for legacy signatures, optionally using the x86_64 SHA512
+//!   instructions when available, with the `sha512-x86` feature (requires
+//!   Rust 1.89)
+//! * Batch verification of many signatures at once
+//! * A public BLAKE2b API, with keyed hashing, salts and personalization
+//! * Selectable Ed25519 validation rules: strict RFC 8032, or ZIP-215
+//! * OpenBSD signify signatures, including embedded messages and signed gzip
+//!   archives
 //! * No external dependencies
 //! * Simple, auditable code
+//! * Optional signing support, with the `sign` feature
+//! * Optional decoding of Minisign secret keys, without the signing code,
+//!   with the `secret-key` feature
+//! * `no_std` support: disable the default `std` feature to only depend on
+//!   `alloc`
 //!
 //! ## Basic Usage
 //!
@@ -41,6 +55,8 @@
 //! ## Loading from Files
 //!
 //! ```rust,no_run
+//! # #[cfg(feature = "std")]
+//! # fn main() {
 //! use minisign_verify::{PublicKey, Signature};
 //! use std::path::Path;
 //!
@@ -59,6 +75,9 @@
 //! public_key
 //!     .verify(&content, &signature, false)
 //!     .expect("Signature didn't verify");
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //!
 //! ## Streaming Verification
@@ -67,6 +86,8 @@
 //! the entire file into memory at once:
 //!
 //! ```rust,no_run
+//! # #[cfg(feature = "std")]
+//! # fn main() {
 //! use minisign_verify::{PublicKey, Signature};
 //! use std::fs::File;
 //! use std::io::{self, Read};
@@ -98,50 +119,175 @@
 //!
 //! // Verify the signature
 //! verifier.finalize().expect("Signature verification failed");
+//! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //!
+//! The same can be done in a single call with `PublicKey::verify_file()`, or
+//! `PublicKey::verify_reader()` for any type implementing `std::io::Read`.
+//!
 //! Note that the streaming verification mode only works with pre-hashed signatures
 //! (the default in newer versions of Minisign).
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod adapters;
 mod base64;
+pub mod blake2b;
 mod crypto;
+mod keyring;
+mod policy;
+#[cfg(feature = "secret-key")]
+mod sign;
+mod signify;
+mod trusted_comment;
 
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
 use std::path::Path;
-use std::{fmt, fs, io};
+#[cfg(feature = "std")]
+use std::{fs, io};
+
+use base64::{Base64, Decoder, Encoder};
 
-use base64::{Base64, Decoder};
+#[cfg(feature = "std")]
+pub use crate::adapters::{VerifyingReader, VerifyingWriter};
+pub use crate::crypto::ed25519::ValidationMode;
+pub use crate::keyring::{KeyRing, ThresholdReport};
+pub use crate::policy::{FileNameMatch, VerificationPolicy};
+#[cfg(feature = "sign")]
+pub use crate::sign::KeyPair;
+#[cfg(feature = "secret-key")]
+pub use crate::sign::SecretKey;
+pub use crate::signify::{SignifyFormat, SignifyGzipHeader, SignifyPublicKey, SignifySignature};
+pub use crate::trusted_comment::{TrustedComment, UnknownFields};
 
-use crate::crypto::blake2b::{Blake2b, BLAKE2B_OUTBYTES};
+use crate::crypto::blake2b::{Blake2b, BLAKE2B_OUTBYTES, BLAKE2B_STATEBYTES};
 use crate::crypto::ed25519;
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// The provided string couldn't be decoded properly
     InvalidEncoding,
+    /// A well-known field of the trusted comment is malformed, duplicated, or
+    /// missing while required by the verification policy
+    InvalidTrustedComment(&'static str),
     /// The signature verification failed
     InvalidSignature,
+    /// The password of an encrypted secret key is incorrect
+    InvalidPassword,
+    /// The secret key is damaged: its checksum or its public key don't match
+    CorruptedSecretKey,
+    /// Fewer distinct keys than required produced a valid signature
+    InsufficientSignatures {
+        /// The number of distinct keys with a valid signature
+        valid: usize,
+        /// The number of distinct keys required
+        required: usize,
+    },
+    /// The verification policy requires a timestamp check, but no clock is
+    /// available
+    ClockUnavailable,
+    /// The trusted timestamp is older than allowed by the verification policy
+    SignatureExpired,
+    /// The trusted timestamp is ahead of the current time by more than the
+    /// allowed clock skew
+    SignatureFromFuture,
     /// An I/O error occurred
+    #[cfg(feature = "std")]
     IoError(io::Error),
     /// The algorithm doesn't match what was expected
     UnexpectedAlgorithm,
+    /// The file name in the trusted comment doesn't match the expected one
+    UnexpectedFileName,
     /// The key ID from the signature doesn't match the public key
     UnexpectedKeyId,
+    /// The input is larger than the configured limit
+    InputTooLarge,
+    /// A saved stream verification state is corrupted, or doesn't match the
+    /// signature
+    InvalidStreamState,
     /// The specified algorithm is not supported by this implementation
     UnsupportedAlgorithm,
     /// Legacy mode is not supported in streaming verification
     UnsupportedLegacyMode,
 }
 
+impl Error {
+    /// Return a stable numeric code for the error
+    ///
+    /// These are the values returned by the C bindings, and will never change.
+    pub fn code(&self) -> i32 {
+        match self {
+            Error::InvalidEncoding => 1,
+            Error::InvalidTrustedComment(_) => 2,
+            Error::InvalidSignature => 3,
+            Error::InsufficientSignatures { .. } => 4,
+            Error::InvalidPassword => 5,
+            Error::CorruptedSecretKey => 6,
+            Error::ClockUnavailable => 7,
+            Error::SignatureExpired => 8,
+            Error::SignatureFromFuture => 9,
+            #[cfg(feature = "std")]
+            Error::IoError(_) => 10,
+            Error::UnexpectedAlgorithm => 11,
+            Error::UnexpectedFileName => 12,
+            Error::UnexpectedKeyId => 13,
+            Error::InputTooLarge => 14,
+            Error::InvalidStreamState => 15,
+            Error::UnsupportedAlgorithm => 16,
+            Error::UnsupportedLegacyMode => 17,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::InvalidEncoding => write!(f, "Invalid encoding in minisign data"),
+            Error::InvalidTrustedComment(field) => {
+                write!(
+                    f,
+                    "Missing or malformed `{}` field in the trusted comment",
+                    field
+                )
+            }
             Error::InvalidSignature => write!(f, "The signature verification failed"),
+            Error::InsufficientSignatures { valid, required } => write!(
+                f,
+                "Only {} valid signature(s) from distinct keys, {} required",
+                valid, required
+            ),
+            Error::InvalidPassword => write!(f, "Wrong password for the secret key"),
+            Error::CorruptedSecretKey => write!(f, "The secret key is corrupted"),
+            Error::ClockUnavailable => write!(f, "No clock is available to check the timestamp"),
+            Error::SignatureExpired => write!(f, "The signature has expired"),
+            Error::SignatureFromFuture => write!(f, "The signature timestamp is in the future"),
+            #[cfg(feature = "std")]
             Error::IoError(e) => write!(f, "I/O error: {}", e),
             Error::UnexpectedAlgorithm => write!(f, "Unexpected signature algorithm"),
+            Error::UnexpectedFileName => write!(
+                f,
+                "The signature was created for a different file than the one provided"
+            ),
             Error::UnexpectedKeyId => write!(
                 f,
                 "The signature was created with a different key than the one provided"
             ),
+            Error::InputTooLarge => write!(f, "The input exceeds the size limit"),
+            Error::InvalidStreamState => write!(f, "Invalid or corrupted stream state"),
             Error::UnsupportedAlgorithm => write!(
                 f,
                 "This signature algorithm is not supported by this implementation"
@@ -153,6 +299,7 @@ impl fmt::Display for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {
     // Note: description() is deprecated in favor of Display implementation
 
@@ -172,12 +319,28 @@ impl From<base64::Error> for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Error {
         Error::IoError(e)
     }
 }
 
+/// The default maximum size of a legacy-signed file that
+/// `PublicKey::verify_file()` loads in memory
+pub const DEFAULT_LEGACY_SIZE_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// Compute the digest that pre-hashed signatures sign: the BLAKE2b-512 hash of
+/// the content
+///
+/// The result can be stored, and later checked with
+/// `PublicKey::verify_prehashed_digest()` without reading the content again.
+pub fn prehash(bin: &[u8]) -> [u8; 64] {
+    let mut h = [0u8; BLAKE2B_OUTBYTES];
+    Blake2b::blake2b(&mut h, bin);
+    h
+}
+
 /// A Minisign public key
 ///
 /// This struct represents a Minisign public key, which can be used to verify
@@ -199,14 +362,46 @@ pub struct PublicKey {
 /// This mode of operation allows for verification of large files by processing them
 /// in chunks, without having to load the entire file into memory.
 ///
-/// Note that this mode only works with pre-hashed signatures (not legacy mode).
+/// Note that this mode only works with pre-hashed signatures, unless legacy
+/// signatures are explicitly allowed with
+/// `VerificationPolicy::allow_legacy_streaming()`.
+///
+/// A stream verifier either borrows its public key, signature and policy, or
+/// owns them. `StreamVerifier<'static>`, as returned by
+/// `PublicKey::into_stream_verifier()` and `StreamVerifier::into_owned()`, can
+/// be stored in long-lived structures and sent to other threads.
 #[derive(Clone)]
 pub struct StreamVerifier<'a> {
-    public_key: &'a PublicKey,
-    signature: &'a Signature,
-    hasher: Blake2b,
+    public_key: Cow<'a, PublicKey>,
+    prepared_key: Option<Cow<'a, ed25519::PreparedKey>>,
+    signature: Cow<'a, Signature>,
+    policy: Cow<'a, VerificationPolicy>,
+    hasher: StreamHasher,
+    len: u64,
+}
+
+const STREAM_STATE_MAGIC: &[u8; 4] = b"MSvs";
+const STREAM_STATE_VERSION: u8 = 1;
+const STREAM_STATE_CHECKSUMBYTES: usize = 32;
+const STREAM_STATE_BYTES: usize = 4 + 1 + 8 + 8 + BLAKE2B_STATEBYTES + STREAM_STATE_CHECKSUMBYTES;
+
+fn stream_state_checksum(state: &[u8], signature: &Signature) -> [u8; STREAM_STATE_CHECKSUMBYTES] {
+    let mut checksum = [0u8; STREAM_STATE_CHECKSUMBYTES];
+    let mut hasher = Blake2b::new(checksum.len());
+    hasher.update(state);
+    hasher.update(&signature.signature);
+    hasher.finalize(&mut checksum);
+    checksum
+}
+
+#[derive(Clone)]
+enum StreamHasher {
+    Prehashed(Blake2b),
+    Legacy(ed25519::Verifier),
 }
 
+pub(crate) static DEFAULT_POLICY: VerificationPolicy = VerificationPolicy::new();
+
 /// A Minisign signature
 ///
 /// This struct represents a Minisign signature, which contains:
@@ -268,6 +463,7 @@ impl Signature {
     }
 
     /// Load a Minisign signature from a `.sig` file
+    #[cfg(feature = "std")]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
         let bin = fs::read_to_string(path)?;
         Signature::decode(&bin)
@@ -278,10 +474,48 @@ impl Signature {
         &self.trusted_comment[17..]
     }
 
+    /// Parse the trusted comment of the signature into its tab-separated
+    /// `key:value` fields
+    ///
+    /// Note that the trusted comment is only authenticated once the signature
+    /// has been verified.
+    pub fn parse_trusted_comment(&self) -> Result<TrustedComment<'_>, Error> {
+        TrustedComment::parse(self.trusted_comment())
+    }
+
     /// Return the untrusted comment of the signature
     pub fn untrusted_comment(&self) -> &str {
         &self.untrusted_comment
     }
+
+    /// Return the ID of the key that created the signature
+    pub fn key_id(&self) -> &[u8; 8] {
+        &self.key_id
+    }
+
+    /// Encode the signature in the format of Minisign `.sig` files
+    ///
+    /// This is the exact inverse of `Signature::decode()`, including the
+    /// trailing newline written by Minisign.
+    pub fn encode(&self) -> String {
+        let mut bin1 = [0u8; 74];
+        bin1[0..2].copy_from_slice(if self.is_prehashed { b"ED" } else { b"Ed" });
+        bin1[2..10].copy_from_slice(&self.key_id);
+        bin1[10..74].copy_from_slice(&self.signature);
+        format!(
+            "{}\n{}\n{}\n{}\n",
+            self.untrusted_comment,
+            Base64::encode_to_string(&bin1[..]).unwrap(),
+            self.trusted_comment,
+            Base64::encode_to_string(&self.global_signature[..]).unwrap()
+        )
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
 }
 
 impl PublicKey {
@@ -321,6 +555,7 @@ impl PublicKey {
     }
 
     /// Load a Minisign key from a file (such as the `minisign.pub` file)
+    #[cfg(feature = "std")]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
         let bin = fs::read_to_string(path)?;
         PublicKey::decode(&bin)
@@ -331,43 +566,221 @@ impl PublicKey {
         self.untrusted_comment.as_deref()
     }
 
-    fn verify_ed25519(&self, bin: &[u8], signature: &Signature) -> Result<(), Error> {
-        if !ed25519::verify(bin, &self.key, &signature.signature) {
+    /// Return the key ID
+    pub fn key_id(&self) -> &[u8; 8] {
+        &self.key_id
+    }
+
+    /// Encode the public key as a base64 string, as accepted by
+    /// `PublicKey::from_base64()`
+    pub fn to_base64(&self) -> String {
+        let mut bin = [0u8; 42];
+        bin[0..2].copy_from_slice(&self.signature_algorithm);
+        bin[2..10].copy_from_slice(&self.key_id);
+        bin[10..42].copy_from_slice(&self.key);
+        Base64::encode_to_string(&bin[..]).unwrap()
+    }
+
+    /// Encode the public key in the format of the `minisign.pub` file
+    ///
+    /// If the key doesn't have an untrusted comment, the default comment
+    /// written by Minisign is used.
+    pub fn encode(&self) -> String {
+        let untrusted_comment = match &self.untrusted_comment {
+            Some(untrusted_comment) => untrusted_comment.clone(),
+            None => format!(
+                "untrusted comment: minisign public key {:016X}",
+                u64::from_le_bytes(self.key_id)
+            ),
+        };
+        format!("{}\n{}\n", untrusted_comment, self.to_base64())
+    }
+
+    fn verify_ed25519(
+        &self,
+        bin: &[u8],
+        signature: &Signature,
+        policy: &VerificationPolicy,
+    ) -> Result<(), Error> {
+        self.verify_ed25519_prepared(None, bin, signature, policy)
+    }
+
+    fn verify_ed25519_prepared(
+        &self,
+        key: Option<&ed25519::PreparedKey>,
+        bin: &[u8],
+        signature: &Signature,
+        policy: &VerificationPolicy,
+    ) -> Result<(), Error> {
+        let mut verifier = ed25519::Verifier::new(&self.key, &signature.signature);
+        verifier.update(bin);
+        if !verifier.finalize_with(key, policy.mode()) {
             return Err(Error::InvalidSignature);
         }
-        let trusted_comment_bin = signature.trusted_comment().as_bytes();
-        let mut global = Vec::with_capacity(signature.signature.len() + trusted_comment_bin.len());
-        global.extend_from_slice(&signature.signature[..]);
-        global.extend_from_slice(trusted_comment_bin);
-        if !ed25519::verify(&global, &self.key, &signature.global_signature) {
+        self.verify_global(key, signature, policy)
+    }
+
+    fn global_verifier(&self, signature: &Signature) -> ed25519::Verifier {
+        let mut verifier = ed25519::Verifier::new(&self.key, &signature.global_signature);
+        verifier.update(&signature.signature);
+        verifier.update(signature.trusted_comment().as_bytes());
+        verifier
+    }
+
+    fn verify_global(
+        &self,
+        key: Option<&ed25519::PreparedKey>,
+        signature: &Signature,
+        policy: &VerificationPolicy,
+    ) -> Result<(), Error> {
+        if !self
+            .global_verifier(signature)
+            .finalize_with(key, policy.mode())
+        {
             return Err(Error::InvalidSignature);
         }
-        Ok(())
+        policy.check(signature)
     }
 
-    /// Verify that `signature` is a valid signature for `bin` using this public
-    /// key `allow_legacy` should only be set to `true` in order to support
-    /// signatures made by older versions of Minisign.
-    pub fn verify(
+    fn verify_prepared(
         &self,
+        key: Option<&ed25519::PreparedKey>,
         bin: &[u8],
         signature: &Signature,
         allow_legacy: bool,
+        policy: &VerificationPolicy,
     ) -> Result<(), Error> {
         if self.key_id != signature.key_id {
             return Err(Error::UnexpectedKeyId);
         }
-        let mut h;
+        let h;
         let bin = if signature.is_prehashed {
-            h = vec![0u8; BLAKE2B_OUTBYTES];
-            Blake2b::blake2b(&mut h, bin);
+            h = prehash(bin);
             &h
         } else if !allow_legacy {
             return Err(Error::UnexpectedAlgorithm);
         } else {
             bin
         };
-        self.verify_ed25519(bin, signature)
+        self.verify_ed25519_prepared(key, bin, signature, policy)
+    }
+
+    /// Verify that `signature` is a valid signature for `bin` using this public
+    /// key `allow_legacy` should only be set to `true` in order to support
+    /// signatures made by older versions of Minisign.
+    pub fn verify(
+        &self,
+        bin: &[u8],
+        signature: &Signature,
+        allow_legacy: bool,
+    ) -> Result<(), Error> {
+        self.verify_with_policy(bin, signature, allow_legacy, &DEFAULT_POLICY)
+    }
+
+    /// Verify that `signature` is a valid signature for `bin` using this public
+    /// key, and that it satisfies the additional checks of `policy`.
+    pub fn verify_with_policy(
+        &self,
+        bin: &[u8],
+        signature: &Signature,
+        allow_legacy: bool,
+        policy: &VerificationPolicy,
+    ) -> Result<(), Error> {
+        self.verify_prepared(None, bin, signature, allow_legacy, policy)
+    }
+
+    /// Verify many `(content, signature)` pairs at once, returning one result
+    /// per pair, in the same order
+    ///
+    /// The results are always the same as the ones of `verify()`. With the
+    /// default validation rules, the signatures are verified one by one. See
+    /// `verify_batch_with_policy()` for a faster alternative.
+    pub fn verify_batch(
+        &self,
+        items: &[(&[u8], &Signature)],
+        allow_legacy: bool,
+    ) -> Vec<Result<(), Error>> {
+        self.verify_batch_with_policy(items, allow_legacy, &DEFAULT_POLICY)
+    }
+
+    /// Verify many `(content, signature)` pairs at once, checking that each
+    /// signature also satisfies `policy`
+    ///
+    /// With `ValidationMode::Zip215`, this is much faster than verifying the
+    /// signatures one by one: they are checked together, and individually
+    /// only if one of them is invalid. The cofactorless equation of the other
+    /// modes can't be checked that way, so they verify each signature
+    /// separately.
+    pub fn verify_batch_with_policy(
+        &self,
+        items: &[(&[u8], &Signature)],
+        allow_legacy: bool,
+        policy: &VerificationPolicy,
+    ) -> Vec<Result<(), Error>> {
+        // Every signature that can be checked contributes two Ed25519
+        // signatures: over the content, and over the trusted comment
+        let mut results = Vec::with_capacity(items.len());
+        let mut verifiers = Vec::with_capacity(items.len() * 2);
+        for &(bin, signature) in items {
+            if self.key_id != signature.key_id {
+                results.push(Err(Error::UnexpectedKeyId));
+                continue;
+            }
+            let mut verifier = ed25519::Verifier::new(&self.key, &signature.signature);
+            if signature.is_prehashed {
+                verifier.update(&prehash(bin));
+            } else if !allow_legacy {
+                results.push(Err(Error::UnexpectedAlgorithm));
+                continue;
+            } else {
+                verifier.update(bin);
+            }
+            verifiers.push(verifier);
+            verifiers.push(self.global_verifier(signature));
+            results.push(Ok(()));
+        }
+        let mut valid = ed25519::Verifier::finalize_batch(&verifiers, policy.mode()).into_iter();
+        for (result, &(_, signature)) in results.iter_mut().zip(items) {
+            if result.is_err() {
+                continue;
+            }
+            let (bin_valid, global_valid) = (valid.next(), valid.next());
+            *result = if bin_valid == Some(true) && global_valid == Some(true) {
+                policy.check(signature)
+            } else {
+                Err(Error::InvalidSignature)
+            };
+        }
+        results
+    }
+
+    /// Verify that `signature` is a valid pre-hashed signature for content
+    /// whose digest, as computed by `prehash()`, is `digest`
+    ///
+    /// Legacy signatures are rejected with `Error::UnexpectedAlgorithm`.
+    pub fn verify_prehashed_digest(
+        &self,
+        digest: &[u8; 64],
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        self.verify_prehashed_digest_with_policy(digest, signature, &DEFAULT_POLICY)
+    }
+
+    /// Verify that `signature` is a valid pre-hashed signature for content
+    /// whose digest is `digest`, and that it satisfies `policy`
+    pub fn verify_prehashed_digest_with_policy(
+        &self,
+        digest: &[u8; 64],
+        signature: &Signature,
+        policy: &VerificationPolicy,
+    ) -> Result<(), Error> {
+        if self.key_id != signature.key_id {
+            return Err(Error::UnexpectedKeyId);
+        }
+        if !signature.is_prehashed {
+            return Err(Error::UnexpectedAlgorithm);
+        }
+        self.verify_ed25519(digest, signature, policy)
     }
 
     /// Sets up a stream verifier that can be use iteratively.
@@ -375,28 +788,369 @@ impl PublicKey {
         &'a self,
         signature: &'a Signature,
     ) -> Result<StreamVerifier<'a>, Error> {
+        self.verify_stream_with_policy(signature, &DEFAULT_POLICY)
+    }
+
+    /// Sets up a stream verifier that will also check the signature against
+    /// `policy` once the data has been processed.
+    pub fn verify_stream_with_policy<'a>(
+        &'a self,
+        signature: &'a Signature,
+        policy: &'a VerificationPolicy,
+    ) -> Result<StreamVerifier<'a>, Error> {
+        StreamVerifier::new(
+            Cow::Borrowed(self),
+            Cow::Borrowed(signature),
+            Cow::Borrowed(policy),
+        )
+    }
+
+    /// Sets up a stream verifier that owns the public key, the signature and
+    /// the policy.
+    ///
+    /// Unlike `verify_stream_with_policy()`, the returned verifier doesn't
+    /// borrow anything, so it can be kept around or moved to another thread.
+    pub fn into_stream_verifier(
+        self,
+        signature: Signature,
+        policy: VerificationPolicy,
+    ) -> Result<StreamVerifier<'static>, Error> {
+        StreamVerifier::new(Cow::Owned(self), Cow::Owned(signature), Cow::Owned(policy))
+    }
+
+    /// Rebuild a stream verifier from a state saved with
+    /// `StreamVerifier::export_state()`
+    ///
+    /// Verification resumes after the bytes that had been processed when the
+    /// state was saved; `StreamVerifier::bytes_processed()` returns that
+    /// offset.
+    ///
+    /// Returns `Error::InvalidStreamState` if the state is corrupted or was
+    /// saved for a different signature, and `Error::InvalidEncoding` if its
+    /// hash state is inconsistent.
+    ///
+    /// The checksum of the state is an unkeyed hash that anyone can
+    /// recompute: it only detects accidental corruption, and doesn't
+    /// authenticate the state.
+    pub fn resume_stream<'a>(
+        &'a self,
+        signature: &'a Signature,
+        state: &[u8],
+    ) -> Result<StreamVerifier<'a>, Error> {
+        self.resume_stream_with_policy(signature, &DEFAULT_POLICY, state)
+    }
+
+    /// Rebuild a stream verifier from a saved state, that will also check
+    /// the signature against `policy` once the data has been processed
+    pub fn resume_stream_with_policy<'a>(
+        &'a self,
+        signature: &'a Signature,
+        policy: &'a VerificationPolicy,
+        state: &[u8],
+    ) -> Result<StreamVerifier<'a>, Error> {
+        let mut stream_verifier = self.verify_stream_with_policy(signature, policy)?;
+        if !signature.is_prehashed {
+            return Err(Error::UnsupportedLegacyMode);
+        }
+        if state.len() != STREAM_STATE_BYTES {
+            return Err(Error::InvalidStreamState);
+        }
+        let (state, checksum) = state.split_at(STREAM_STATE_BYTES - STREAM_STATE_CHECKSUMBYTES);
+        if stream_state_checksum(state, signature)[..] != checksum[..]
+            || &state[0..4] != STREAM_STATE_MAGIC
+            || state[4] != STREAM_STATE_VERSION
+            || state[5..13] != signature.key_id
+        {
+            return Err(Error::InvalidStreamState);
+        }
+        let mut len = [0u8; 8];
+        len.copy_from_slice(&state[13..21]);
+        let len = u64::from_le_bytes(len);
+        let mut hasher_state = [0u8; BLAKE2B_STATEBYTES];
+        hasher_state.copy_from_slice(&state[21..]);
+        let hasher = Blake2b::import_state(BLAKE2B_OUTBYTES, &hasher_state)?;
+        if hasher.absorbed() != len as u128 {
+            return Err(Error::InvalidStreamState);
+        }
+        stream_verifier.hasher = StreamHasher::Prehashed(hasher);
+        stream_verifier.len = len;
+        Ok(stream_verifier)
+    }
+
+    /// Verify that `signature` is a valid signature for the content of
+    /// `reader`, streaming it through a `StreamVerifier`.
+    ///
+    /// Returns the number of bytes that were read. Only pre-hashed signatures
+    /// are supported.
+    #[cfg(feature = "std")]
+    pub fn verify_reader<R: Read>(&self, reader: R, signature: &Signature) -> Result<u64, Error> {
+        let mut stream_verifier = self.verify_stream(signature)?;
+        let len = stream_verifier.update_from_reader(reader)?;
+        stream_verifier.finalize()?;
+        Ok(len)
+    }
+
+    /// Verify that `signature` is a valid signature for the file at `path`.
+    ///
+    /// Pre-hashed signatures are verified in a streaming fashion. Legacy
+    /// signatures are only accepted if `allow_legacy` is `true`. They require
+    /// the whole file to be loaded in memory, and are only accepted for files
+    /// up to `DEFAULT_LEGACY_SIZE_LIMIT` bytes. Use
+    /// `verify_file_with_limit()` for a different limit.
+    ///
+    /// Returns the size of the file.
+    #[cfg(feature = "std")]
+    pub fn verify_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        signature: &Signature,
+        allow_legacy: bool,
+    ) -> Result<u64, Error> {
+        let legacy_size_limit = if allow_legacy {
+            DEFAULT_LEGACY_SIZE_LIMIT
+        } else {
+            0
+        };
+        self.verify_file_with_limit(path, signature, legacy_size_limit)
+    }
+
+    /// Verify that `signature` is a valid signature for the file at `path`,
+    /// loading legacy-signed files in memory only if they are at most
+    /// `legacy_size_limit` bytes long.
+    ///
+    /// A limit of `0` rejects legacy signatures.
+    #[cfg(feature = "std")]
+    pub fn verify_file_with_limit<P: AsRef<Path>>(
+        &self,
+        path: P,
+        signature: &Signature,
+        legacy_size_limit: u64,
+    ) -> Result<u64, Error> {
+        let file = File::open(path)?;
+        if signature.is_prehashed {
+            return self.verify_reader(file, signature);
+        }
         if self.key_id != signature.key_id {
             return Err(Error::UnexpectedKeyId);
         }
-        if !signature.is_prehashed {
-            return Err(Error::UnsupportedLegacyMode);
+        if legacy_size_limit == 0 {
+            return Err(Error::UnexpectedAlgorithm);
+        }
+        let mut bin = vec![];
+        file.take(legacy_size_limit.saturating_add(1))
+            .read_to_end(&mut bin)?;
+        if bin.len() as u64 > legacy_size_limit {
+            return Err(Error::InputTooLarge);
+        }
+        self.verify(&bin, signature, true)?;
+        Ok(bin.len() as u64)
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+/// A public key prepared for verifying many signatures
+///
+/// Every verification with a `PublicKey` has to decode the Ed25519 point,
+/// and compute a table of its multiples. A `PreparedPublicKey` does it only
+/// once, which makes verifications faster when the same key is used
+/// repeatedly, for example for release keys.
+#[derive(Clone)]
+pub struct PreparedPublicKey {
+    public_key: PublicKey,
+    key: ed25519::PreparedKey,
+}
+
+impl PreparedPublicKey {
+    /// Prepare `public_key` for repeated verifications
+    pub fn new(public_key: PublicKey) -> Self {
+        let key = ed25519::PreparedKey::new(&public_key.key);
+        PreparedPublicKey { public_key, key }
+    }
+
+    /// Return the public key
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Verify that `signature` is a valid signature for `bin`, like
+    /// `PublicKey::verify()`
+    pub fn verify(
+        &self,
+        bin: &[u8],
+        signature: &Signature,
+        allow_legacy: bool,
+    ) -> Result<(), Error> {
+        self.verify_with_policy(bin, signature, allow_legacy, &DEFAULT_POLICY)
+    }
+
+    /// Verify that `signature` is a valid signature for `bin`, and that it
+    /// satisfies `policy`, like `PublicKey::verify_with_policy()`
+    pub fn verify_with_policy(
+        &self,
+        bin: &[u8],
+        signature: &Signature,
+        allow_legacy: bool,
+        policy: &VerificationPolicy,
+    ) -> Result<(), Error> {
+        self.public_key
+            .verify_prepared(Some(&self.key), bin, signature, allow_legacy, policy)
+    }
+
+    /// Sets up a stream verifier that uses the prepared key
+    pub fn verify_stream<'a>(
+        &'a self,
+        signature: &'a Signature,
+    ) -> Result<StreamVerifier<'a>, Error> {
+        self.verify_stream_with_policy(signature, &DEFAULT_POLICY)
+    }
+
+    /// Sets up a stream verifier that uses the prepared key, and will also
+    /// check the signature against `policy`
+    pub fn verify_stream_with_policy<'a>(
+        &'a self,
+        signature: &'a Signature,
+        policy: &'a VerificationPolicy,
+    ) -> Result<StreamVerifier<'a>, Error> {
+        let mut stream_verifier = self
+            .public_key
+            .verify_stream_with_policy(signature, policy)?;
+        stream_verifier.prepared_key = Some(Cow::Borrowed(&self.key));
+        Ok(stream_verifier)
+    }
+}
+
+impl From<PublicKey> for PreparedPublicKey {
+    fn from(public_key: PublicKey) -> Self {
+        PreparedPublicKey::new(public_key)
+    }
+}
+
+impl fmt::Debug for PreparedPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreparedPublicKey")
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
+
+impl<'a> StreamVerifier<'a> {
+    fn new(
+        public_key: Cow<'a, PublicKey>,
+        signature: Cow<'a, Signature>,
+        policy: Cow<'a, VerificationPolicy>,
+    ) -> Result<Self, Error> {
+        if public_key.key_id != signature.key_id {
+            return Err(Error::UnexpectedKeyId);
         }
-        let hasher = Blake2b::new(BLAKE2B_OUTBYTES);
+        let hasher = if signature.is_prehashed {
+            StreamHasher::Prehashed(Blake2b::new(BLAKE2B_OUTBYTES))
+        } else if policy.allows_legacy_streaming() {
+            StreamHasher::Legacy(ed25519::Verifier::new(
+                &public_key.key,
+                &signature.signature,
+            ))
+        } else {
+            return Err(Error::UnsupportedLegacyMode);
+        };
         Ok(StreamVerifier {
-            public_key: self,
+            public_key,
+            prepared_key: None,
             signature,
+            policy,
             hasher,
+            len: 0,
         })
     }
-}
 
-impl StreamVerifier<'_> {
+    /// Return the public key used to verify the signature
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Return the signature being verified
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Convert into a verifier that owns its public key, signature and
+    /// policy, cloning them if they were borrowed
+    ///
+    /// The data processed so far is preserved.
+    pub fn into_owned(self) -> StreamVerifier<'static> {
+        StreamVerifier {
+            public_key: Cow::Owned(self.public_key.into_owned()),
+            prepared_key: self
+                .prepared_key
+                .map(|prepared_key| Cow::Owned(prepared_key.into_owned())),
+            signature: Cow::Owned(self.signature.into_owned()),
+            policy: Cow::Owned(self.policy.into_owned()),
+            hasher: self.hasher,
+            len: self.len,
+        }
+    }
+
+    /// Return the number of bytes processed so far
+    pub fn bytes_processed(&self) -> u64 {
+        self.len
+    }
+
+    /// Save the state of the verifier, so that verification can be resumed
+    /// later with `PublicKey::resume_stream()`, even in another process
+    ///
+    /// The state includes the number of bytes processed so far, and is bound
+    /// to the signature. It is protected by a checksum that detects
+    /// accidental corruption, but it is not authenticated: it has to be
+    /// stored somewhere at least as trustworthy as the data itself.
+    ///
+    /// Only pre-hashed signatures can be saved.
+    pub fn export_state(&self) -> Result<Vec<u8>, Error> {
+        let hasher = match &self.hasher {
+            StreamHasher::Prehashed(hasher) => hasher,
+            StreamHasher::Legacy(_) => return Err(Error::UnsupportedLegacyMode),
+        };
+        let mut state = Vec::with_capacity(STREAM_STATE_BYTES);
+        state.extend_from_slice(STREAM_STATE_MAGIC);
+        state.push(STREAM_STATE_VERSION);
+        state.extend_from_slice(&self.signature.key_id);
+        state.extend_from_slice(&self.len.to_le_bytes());
+        state.extend_from_slice(&hasher.export_state());
+        let checksum = stream_state_checksum(&state, &self.signature);
+        state.extend_from_slice(&checksum);
+        Ok(state)
+    }
+
+    #[cfg(feature = "std")]
+    fn update_from_reader<R: Read>(&mut self, mut reader: R) -> Result<u64, Error> {
+        let mut buf = [0u8; 65536];
+        let mut len = 0u64;
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => return Ok(len),
+                Ok(n) => {
+                    self.update(&buf[..n]);
+                    len += n as u64;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::IoError(e)),
+            }
+        }
+    }
+
     /// Update the verifier with a chunk of data
     ///
     /// This method can be called multiple times with different chunks of the file
     /// to be verified. The chunks will be hashed incrementally.
     pub fn update(&mut self, buf: &[u8]) {
-        self.hasher.update(buf);
+        self.len = self.len.wrapping_add(buf.len() as u64);
+        match &mut self.hasher {
+            StreamHasher::Prehashed(hasher) => hasher.update(buf),
+            StreamHasher::Legacy(verifier) => verifier.update(buf),
+        }
     }
 
     /// Finalize the verification process
@@ -406,14 +1160,30 @@ impl StreamVerifier<'_> {
     ///
     /// Returns `Ok(())` if the signature is valid, or an error otherwise.
     pub fn finalize(&mut self) -> Result<(), Error> {
-        let mut bin = vec![0u8; BLAKE2B_OUTBYTES];
-        self.hasher.finalize(&mut bin);
-        self.public_key.verify_ed25519(&bin, self.signature)
+        let key = self.prepared_key.as_deref();
+        match &mut self.hasher {
+            StreamHasher::Prehashed(hasher) => {
+                let mut bin = [0u8; BLAKE2B_OUTBYTES];
+                hasher.finalize(&mut bin);
+                self.public_key
+                    .verify_ed25519_prepared(key, &bin, &self.signature, &self.policy)
+            }
+            StreamHasher::Legacy(verifier) => {
+                if !verifier.finalize_with(key, self.policy.mode()) {
+                    return Err(Error::InvalidSignature);
+                }
+                self.public_key
+                    .verify_global(key, &self.signature, &self.policy)
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::num::NonZeroUsize;
+    use std::time::Duration;
+
     use super::*;
     #[test]
     fn verify() {
@@ -489,6 +1259,825 @@ y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+b
             .expect("Signature didn't verify");
     }
 
+    #[test]
+    fn reject_non_canonical_s() {
+        let public_key =
+            PublicKey::from_base64("RWQBI0VniavN7+pKbGPinFIKvvVQexMuxfmVR3auvr57kkIe6mkURtIs")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQBI0VniavN7xh0od4foi9vkvb4iisNrDD8+mHIhwhSI7T4vRrHtGY5mLCnK5qqaRTd29Unl3vY9jMAiPhE5/\
+             iowuCEI5mb8A0=
+trusted comment: timestamp:1700000000\tfile:test\tprehashed
+MS0M756mNybVGUcPZgpp/OFv0deYH4iVYq2T76oMcycv4JZrPCfEntiXGbuG0Qv3K+KGRMT6r5SDdQzPNRDaDA==",
+        )
+        .expect("Unable to decode the signature");
+        let bin = b"test";
+        public_key
+            .verify(&bin[..], &signature, false)
+            .expect("Signature didn't verify");
+
+        // Same signature, with L added to S
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQBI0VniavN7xh0od4foi9vkvb4iisNrDD8+mHIhwhSI7T4vRrHtGY5hYSdiLQNfGyzeM3KdXW3CzQAiPhE5/\
+             iowuCEI5mb8B0=
+trusted comment: timestamp:1700000000\tfile:test\tprehashed
+nec81umoVsFvcHLUZ59QM5qIACEDe1vcW6hMWx3FrqyLQwfPABSHpi2gkPJtmKe2e8SOQ83+jp2SznT15AHDBg==",
+        )
+        .expect("Unable to decode the signature");
+        match public_key.verify(&bin[..], &signature, false) {
+            Err(Error::InvalidSignature) => {}
+            _ => panic!("Non-canonical signature verified"),
+        }
+    }
+
+    #[test]
+    fn parse_trusted_comment() {
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/\
+             z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==",
+        )
+        .expect("Unable to decode the signature");
+        let trusted_comment = signature
+            .parse_trusted_comment()
+            .expect("Unable to parse the trusted comment");
+        assert_eq!(trusted_comment.timestamp(), Some(1556193335));
+        assert_eq!(trusted_comment.file(), Some("test"));
+        assert!(!trusted_comment.is_prehashed());
+        assert_eq!(trusted_comment.unknown_fields().count(), 0);
+        assert_eq!(trusted_comment.as_str(), signature.trusted_comment());
+    }
+
+    #[test]
+    fn verify_with_policy() {
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/\
+             z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==",
+        )
+        .expect("Unable to decode the signature");
+        let bin = b"test";
+        let policy = VerificationPolicy::new()
+            .max_age(Duration::from_secs(3600))
+            .max_clock_skew(Duration::from_secs(60));
+
+        public_key
+            .verify_with_policy(
+                &bin[..],
+                &signature,
+                false,
+                &policy.clone().current_time(1556193335 + 3600),
+            )
+            .expect("Signature didn't verify");
+        public_key
+            .verify_with_policy(
+                &bin[..],
+                &signature,
+                false,
+                &policy.clone().current_time(1556193335 - 60),
+            )
+            .expect("Signature didn't verify");
+        match public_key.verify_with_policy(
+            &bin[..],
+            &signature,
+            false,
+            &policy.clone().current_time(1556193335 + 3600 + 61),
+        ) {
+            Err(Error::SignatureExpired) => {}
+            _ => panic!("Expired signature verified"),
+        };
+        match public_key.verify_with_policy(
+            &bin[..],
+            &signature,
+            false,
+            &policy.clone().current_time(1556193335 - 61),
+        ) {
+            Err(Error::SignatureFromFuture) => {}
+            _ => panic!("Signature from the future verified"),
+        };
+        match public_key.verify_with_policy(
+            &b"Test"[..],
+            &signature,
+            false,
+            &policy.clone().current_time(1556193335 + 3600 + 61),
+        ) {
+            Err(Error::InvalidSignature) => {}
+            _ => panic!("Invalid signature verified"),
+        };
+
+        let policy = policy.current_time(1556193335 + 3600 + 61);
+        let mut stream_verifier = public_key
+            .verify_stream_with_policy(&signature, &policy)
+            .expect("Can't extract StreamerVerifier");
+        stream_verifier.update(&bin[..]);
+        match stream_verifier.finalize() {
+            Err(Error::SignatureExpired) => {}
+            _ => panic!("Expired signature verified"),
+        };
+
+        let policy = VerificationPolicy::new()
+            .max_age(Duration::from_secs(3600))
+            .current_time(1556193335 - 1);
+        match public_key.verify_with_policy(&bin[..], &signature, false, &policy) {
+            Err(Error::SignatureFromFuture) => {}
+            _ => panic!("Signature from the future verified"),
+        };
+    }
+
+    #[test]
+    fn verify_file_name() {
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/\
+             z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==",
+        )
+        .expect("Unable to decode the signature");
+        let bin = b"test";
+        for (name, mode) in [
+            ("test", FileNameMatch::Exact),
+            ("test", FileNameMatch::Basename),
+            ("/tmp/downloads/test", FileNameMatch::Basename),
+        ] {
+            let policy = VerificationPolicy::new().expected_file_name(name, mode);
+            public_key
+                .verify_with_policy(&bin[..], &signature, false, &policy)
+                .expect("Signature didn't verify");
+        }
+        for (name, mode) in [
+            ("/tmp/downloads/test", FileNameMatch::Exact),
+            ("test2", FileNameMatch::Basename),
+            ("test/other", FileNameMatch::Basename),
+        ] {
+            let policy = VerificationPolicy::new().expected_file_name(name, mode);
+            match public_key.verify_with_policy(&bin[..], &signature, false, &policy) {
+                Err(Error::UnexpectedFileName) => {}
+                _ => panic!("Signature for a different file verified"),
+            };
+        }
+    }
+
+    #[test]
+    fn verify_keyring() {
+        let keyring = KeyRing::decode(
+            "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+
+RWQBI0VniavN7wABAgMEBQYHCAkKCwwNDg8QERITFBUWFxgZGhscHR4f
+",
+        )
+        .expect("Unable to decode the key ring");
+        assert_eq!(keyring.len(), 2);
+        let public_key = keyring
+            .get(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef])
+            .expect("Missing key");
+        assert_eq!(public_key.untrusted_comment(), None);
+
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/\
+             z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==",
+        )
+        .expect("Unable to decode the signature");
+        let bin = b"test";
+        let public_key = keyring
+            .verify(&bin[..], &signature, false)
+            .expect("Signature didn't verify");
+        assert_eq!(
+            public_key.untrusted_comment(),
+            Some("untrusted comment: minisign public key E7620F1842B4E81F")
+        );
+        let mut stream_verifier = keyring
+            .verify_stream(&signature)
+            .expect("Can't extract StreamerVerifier");
+        assert_eq!(stream_verifier.public_key(), public_key);
+        stream_verifier.update(&bin[..]);
+        stream_verifier.finalize().expect("Signature didn't verify");
+
+        let mut keyring = keyring;
+        keyring.remove(signature.key_id());
+        match keyring.verify(&bin[..], &signature, false) {
+            Err(Error::UnexpectedKeyId) => {}
+            _ => panic!("Signature verified with a missing key"),
+        };
+
+        #[cfg(feature = "std")]
+        {
+            let dir = std::env::temp_dir()
+                .join(format!("minisign-verify-keyring-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("a.pub"),
+                "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+",
+            )
+            .unwrap();
+            fs::write(dir.join("b.txt"), "not a key").unwrap();
+            let keyring = KeyRing::from_dir(&dir);
+            fs::remove_dir_all(&dir).unwrap();
+            let keyring = keyring.expect("Unable to load the key ring");
+            assert_eq!(keyring.len(), 1);
+            keyring
+                .verify(&bin[..], &signature, false)
+                .expect("Signature didn't verify");
+        }
+    }
+
+    #[test]
+    fn verify_threshold() {
+        let keyring = KeyRing::decode(
+            "RWQBAQEBAQEBAYqI4910CfGV/VLbLTy6XXLKZwm/HZQSG/N0iAG0D29c
+RWQCAgICAgICAoE5dw6ofRdfVqNUZsNMfszLjYqRtO43ol32D1uPybOU",
+        )
+        .expect("Unable to decode the key ring");
+        let signatures = [
+            "untrusted comment: signature from minisign secret key
+RUQBAQEBAQEBAU5h/WtOasu11/xhHix8x/oD+xrOqfPEuY5X9QvtMo9rm5aQwMqUQIxKlku47dvyJciH2pOPs7zLF2LpHKlnVgg=
+trusted comment: timestamp:1700000000\tfile:release\thashed
+BDaHSNoy1YBjuTr3cNYNwWlSdfjA8tiddg32rJLlOf10qW7D4EwZAJGGfoypTzWU0pasuN2hKmxlQ+4odDgeCg==",
+            "untrusted comment: signature from minisign secret key
+RUQBAQEBAQEBAU5h/WtOasu11/xhHix8x/oD+xrOqfPEuY5X9QvtMo9rm5aQwMqUQIxKlku47dvyJciH2pOPs7zLF2LpHKlnVgg=
+trusted comment: timestamp:1700000000\tfile:release\thashed
+BDaHSNoy1YBjuTr3cNYNwWlSdfjA8tiddg32rJLlOf10qW7D4EwZAJGGfoypTzWU0pasuN2hKmxlQ+4odDgeCg==",
+            "untrusted comment: signature from minisign secret key
+RUQDAwMDAwMDAwelFMlgKyr5us7w/9rZqd5IZGwYRD69teI9NE50sMwKpNMFAjtrZ38jPWU2FEfooySiZ9x8CiNEqI03q6rU7wo=
+trusted comment: timestamp:1700000000\tfile:release\thashed
+neyUAP/6RIpzggzcWkpFcPKSUsortUUwLKCIM5XZKLyg2U1pIAgBCDBJB9UBgQ4c3C0hFJP/CW+yBh0EZrKwBQ==",
+            "untrusted comment: signature from minisign secret key
+RUQCAgICAgICAmHjTqGEXgDv9X98AmKFz2HBd9H7FnQll29kCEvD8uMvaKMklC2GY9AVgS6IyPjF9kZ+VET0GtHo2+6cT+gnOwg=
+trusted comment: timestamp:1700000000\tfile:release\thashed
+wWeSm22ycMtsvQ7ZVb8VD0n3FPlZ3OQmTOeTg2p7q2ZS9IcQyxq86l2pap5VbJ1P6NefwftM5cNEXXgEPnbNDg==",
+        ]
+        .iter()
+        .map(|s| Signature::decode(s).expect("Unable to decode the signature"))
+        .collect::<Vec<_>>();
+        let bin = b"release";
+        let threshold = |n| NonZeroUsize::new(n).unwrap();
+
+        let report = keyring
+            .verify_threshold(&bin[..], &signatures, threshold(2), false)
+            .expect("Threshold not met");
+        assert_eq!(report.signers(), &[[1u8; 8], [2u8; 8]]);
+        assert_eq!(report.invalid_signatures(), &[2]);
+
+        match keyring.verify_threshold(&bin[..], &signatures, threshold(3), false) {
+            Err(Error::InsufficientSignatures {
+                valid: 2,
+                required: 3,
+            }) => {}
+            _ => panic!("Threshold verified with too few signatures"),
+        };
+        match keyring.verify_threshold(&b"Release"[..], &signatures, threshold(1), false) {
+            Err(Error::InsufficientSignatures {
+                valid: 0,
+                required: 1,
+            }) => {}
+            _ => panic!("Invalid signatures counted"),
+        };
+
+        let policy = VerificationPolicy::new().expected_file_name("other", FileNameMatch::Exact);
+        match keyring.verify_threshold_with_policy(
+            &bin[..],
+            &signatures,
+            threshold(1),
+            false,
+            &policy,
+        ) {
+            Err(Error::InsufficientSignatures {
+                valid: 0,
+                required: 1,
+            }) => {}
+            _ => panic!("Signatures violating the policy counted"),
+        };
+        let policy = VerificationPolicy::new().expected_file_name("release", FileNameMatch::Exact);
+        let report = keyring
+            .verify_threshold_with_policy(&bin[..], &signatures, threshold(2), false, &policy)
+            .expect("Threshold not met");
+        assert_eq!(report.signers(), &[[1u8; 8], [2u8; 8]]);
+    }
+
+    #[test]
+    fn encode() {
+        let public_key_str = "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+";
+        let public_key =
+            PublicKey::decode(public_key_str).expect("Unable to decode the public key");
+        assert_eq!(public_key.encode(), public_key_str);
+        let public_key = PublicKey::from_base64(&public_key.to_base64())
+            .expect("Unable to decode the public key");
+        assert_eq!(public_key.to_string(), public_key_str);
+
+        for signature_str in [
+            "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==
+",
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/\
+             z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==
+",
+        ] {
+            let signature =
+                Signature::decode(signature_str).expect("Unable to decode the signature");
+            assert_eq!(signature.encode(), signature_str);
+            assert_eq!(signature.to_string(), signature_str);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn verify_reader_and_file() {
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/\
+             z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==",
+        )
+        .expect("Unable to decode the signature");
+        let legacy_signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==",
+        )
+        .expect("Unable to decode the signature");
+        let len = public_key
+            .verify_reader(&b"test"[..], &signature)
+            .expect("Signature didn't verify");
+        assert_eq!(len, 4);
+        match public_key.verify_reader(&b"Test"[..], &signature) {
+            Err(Error::InvalidSignature) => {}
+            _ => panic!("Invalid signature verified"),
+        };
+        match public_key.verify_reader(&b"test"[..], &legacy_signature) {
+            Err(Error::UnsupportedLegacyMode) => {}
+            _ => panic!("Legacy signature verified"),
+        };
+
+        let path =
+            std::env::temp_dir().join(format!("minisign-verify-file-{}", std::process::id()));
+        fs::write(&path, b"test").unwrap();
+        let results = (
+            public_key.verify_file(&path, &signature, false),
+            public_key.verify_file(&path, &legacy_signature, true),
+            public_key.verify_file_with_limit(&path, &legacy_signature, 3),
+            public_key.verify_file_with_limit(&path, &legacy_signature, 0),
+            public_key.verify_file(&path, &legacy_signature, false),
+        );
+        fs::remove_file(&path).unwrap();
+        assert_eq!(results.0.expect("Signature didn't verify"), 4);
+        assert_eq!(results.1.expect("Signature didn't verify"), 4);
+        match results.2 {
+            Err(Error::InputTooLarge) => {}
+            _ => panic!("Size limit ignored"),
+        };
+        match results.3 {
+            Err(Error::UnexpectedAlgorithm) => {}
+            _ => panic!("Legacy signature verified"),
+        };
+        match results.4 {
+            Err(Error::UnexpectedAlgorithm) => {}
+            _ => panic!("Legacy signature verified without opting in"),
+        };
+        match public_key.verify_file(&path, &signature, false) {
+            Err(Error::IoError(_)) => {}
+            _ => panic!("Missing file verified"),
+        };
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn verifying_adapters() {
+        use std::io::{self, Read, Write};
+
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/\
+             z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==",
+        )
+        .expect("Unable to decode the signature");
+
+        for (bin, valid) in [(&b"test"[..], true), (&b"Test"[..], false)] {
+            let stream_verifier = public_key
+                .verify_stream(&signature)
+                .expect("Can't extract StreamerVerifier");
+            let mut reader = VerifyingReader::new(bin, stream_verifier);
+            let mut content = vec![];
+            reader.read_to_end(&mut content).unwrap();
+            assert_eq!(content, bin);
+            assert_eq!(reader.finish().is_ok(), valid);
+
+            let stream_verifier = public_key
+                .verify_stream(&signature)
+                .expect("Can't extract StreamerVerifier");
+            let mut writer = VerifyingWriter::new(vec![], stream_verifier);
+            io::copy(&mut &bin[..], &mut writer).unwrap();
+            writer.flush().unwrap();
+            match writer.finish() {
+                Ok(content) => {
+                    assert!(valid);
+                    assert_eq!(content, bin);
+                }
+                Err(Error::InvalidSignature) => assert!(!valid),
+                Err(e) => panic!("Unexpected error: {}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn verify_stream_owned() {
+        fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==",
+        )
+        .expect("Unable to decode the signature");
+
+        let mut stream_verifier = public_key
+            .verify_stream(&signature)
+            .expect("Can't extract StreamerVerifier");
+        stream_verifier.update(b"te");
+        let mut stream_verifier = stream_verifier.into_owned();
+        assert_send_static(&stream_verifier);
+        let handle = std::thread::spawn(move || {
+            stream_verifier.update(b"st");
+            stream_verifier.finalize()
+        });
+        handle
+            .join()
+            .unwrap()
+            .expect("Signature with owned StreamVerifier didn't verify");
+
+        let mut stream_verifier = public_key
+            .clone()
+            .into_stream_verifier(signature.clone(), VerificationPolicy::new())
+            .expect("Can't extract StreamerVerifier");
+        assert_send_static(&stream_verifier);
+        assert_eq!(stream_verifier.public_key(), &public_key);
+        assert_eq!(stream_verifier.signature().key_id(), signature.key_id());
+        stream_verifier.update(b"Test");
+        assert!(stream_verifier.finalize().is_err());
+    }
+
+    #[test]
+    fn verify_prehashed_digest() {
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==",
+        )
+        .expect("Unable to decode the signature");
+        let digest = prehash(b"test");
+        public_key
+            .verify_prehashed_digest(&digest, &signature)
+            .expect("Signature didn't verify");
+        assert!(public_key
+            .verify_prehashed_digest(&prehash(b"Test"), &signature)
+            .is_err());
+
+        let legacy_signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==",
+        )
+        .expect("Unable to decode the signature");
+        match public_key.verify_prehashed_digest(&digest, &legacy_signature) {
+            Err(Error::UnexpectedAlgorithm) => {}
+            _ => panic!("Legacy signature accepted"),
+        }
+    }
+
+    #[test]
+    fn verify_batch() {
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==",
+        )
+        .expect("Unable to decode the signature");
+        let legacy_signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==",
+        )
+        .expect("Unable to decode the signature");
+        let mut forged_comment = signature.clone();
+        forged_comment.trusted_comment.push('!');
+        let other_key_signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQBAQEBAQEBAU5h/WtOasu11/xhHix8x/oD+xrOqfPEuY5X9QvtMo9rm5aQwMqUQIxKlku47dvyJciH2pOPs7zLF2LpHKlnVgg=
+trusted comment: timestamp:1700000000\tfile:release\thashed
+BDaHSNoy1YBjuTr3cNYNwWlSdfjA8tiddg32rJLlOf10qW7D4EwZAJGGfoypTzWU0pasuN2hKmxlQ+4odDgeCg==",
+        )
+        .expect("Unable to decode the signature");
+
+        let items: [(&[u8], &Signature); 6] = [
+            (b"test", &signature),
+            (b"test", &legacy_signature),
+            (b"Test", &signature),
+            (b"test", &forged_comment),
+            (b"test", &other_key_signature),
+            (b"test", &signature),
+        ];
+        let results = public_key.verify_batch(&items, true);
+        assert_eq!(results.len(), items.len());
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        assert!(matches!(results[2], Err(Error::InvalidSignature)));
+        assert!(matches!(results[3], Err(Error::InvalidSignature)));
+        assert!(matches!(results[4], Err(Error::UnexpectedKeyId)));
+        assert!(results[5].is_ok());
+
+        let results = public_key.verify_batch(&items[0..2], false);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::UnexpectedAlgorithm)));
+
+        let policy = VerificationPolicy::new().expected_file_name("other", FileNameMatch::Exact);
+        assert!(public_key.verify_batch_with_policy(&items[0..1], true, &policy)[0].is_err());
+        assert!(public_key.verify_batch(&[], false).is_empty());
+    }
+
+    #[test]
+    fn verify_batch_torsion() {
+        let public_key =
+            PublicKey::from_base64("RWQBI0VniavN7+pKbGPinFIKvvVQexMuxfmVR3auvr57kkIe6mkURtIs")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQBI0VniavN7xh0od4foi9vkvb4iisNrDD8+mHIhwhSI7T4vRrHtGY5mLCnK5qqaRTd29Unl3vY9jMAiPhE5/\
+             iowuCEI5mb8A0=
+trusted comment: timestamp:1700000000\tfile:test\tprehashed
+MS0M756mNybVGUcPZgpp/OFv0deYH4iVYq2T76oMcycv4JZrPCfEntiXGbuG0Qv3K+KGRMT6r5SDdQzPNRDaDA==",
+        )
+        .expect("Unable to decode the signature");
+        // R has a component of order 8: only a cofactored equation accepts it
+        let torsion_signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQBI0VniavN7641wGfJQ/ZpVR+VB+bS2Uh7Y0AN9s9Z0IYTVNO9abhu6djlslD1JOn300f8mZVCmC4+OP1tkBmvKEnA3M558Ag=
+trusted comment: timestamp:1700000000\tfile:test\tprehashed
+v5HwPEY1GFF+lJ26FCerDzXZhdEhC0bSF0t+GAXxL74gbk1EnTMLP2FjqfcPSPFQJcb4agOTj/gCTE1OzKX3Cg==",
+        )
+        .expect("Unable to decode the signature");
+
+        let items: [(&[u8], &Signature); 2] =
+            [(b"test", &signature), (b"test", &torsion_signature)];
+        let results = public_key.verify_batch(&items, false);
+        for (result, &(bin, signature)) in results.iter().zip(items.iter()) {
+            assert_eq!(
+                result.is_ok(),
+                public_key.verify(bin, signature, false).is_ok()
+            );
+        }
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::InvalidSignature)));
+    }
+
+    #[test]
+    fn verify_prepared() {
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let prepared_public_key = PreparedPublicKey::new(public_key.clone());
+        assert_eq!(prepared_public_key.public_key(), &public_key);
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==",
+        )
+        .expect("Unable to decode the signature");
+        let legacy_signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==",
+        )
+        .expect("Unable to decode the signature");
+
+        prepared_public_key
+            .verify(b"test", &signature, false)
+            .expect("Signature didn't verify");
+        prepared_public_key
+            .verify(b"test", &legacy_signature, true)
+            .expect("Signature didn't verify");
+        assert!(matches!(
+            prepared_public_key.verify(b"Test", &signature, false),
+            Err(Error::InvalidSignature)
+        ));
+        assert!(matches!(
+            prepared_public_key.verify(b"test", &legacy_signature, false),
+            Err(Error::UnexpectedAlgorithm)
+        ));
+
+        for (chunks, valid) in [([&b"te"[..], b"st"], true), ([b"Te", b"st"], false)] {
+            let mut stream_verifier = prepared_public_key
+                .verify_stream(&signature)
+                .expect("Unable to create stream verifier");
+            for chunk in chunks {
+                stream_verifier.update(chunk);
+            }
+            let owned = stream_verifier.clone().into_owned();
+            assert_eq!(stream_verifier.finalize().is_ok(), valid);
+            let mut owned = std::thread::spawn(move || owned).join().unwrap();
+            assert_eq!(owned.finalize().is_ok(), valid);
+        }
+
+        // Keys that are not valid points reject every signature
+        let invalid_key = PreparedPublicKey::new(PublicKey {
+            key: [0u8; 32],
+            ..public_key
+        });
+        assert!(matches!(
+            invalid_key.verify(b"test", &signature, false),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn verify_validation_mode() {
+        // The identity as a public key, with R = identity and S = 0: valid
+        // for any message with the cofactored equation only
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let mut weak_signature = [0u8; 64];
+        weak_signature[..32].copy_from_slice(&identity);
+        let public_key = PublicKey {
+            untrusted_comment: None,
+            signature_algorithm: *b"Ed",
+            key_id: [1u8; 8],
+            key: identity,
+        };
+        let signature = Signature {
+            untrusted_comment: "untrusted comment: weak".to_string(),
+            key_id: [1u8; 8],
+            signature: weak_signature,
+            trusted_comment: "trusted comment: timestamp:0".to_string(),
+            global_signature: weak_signature,
+            is_prehashed: true,
+        };
+        assert!(matches!(
+            public_key.verify(b"anything", &signature, false),
+            Err(Error::InvalidSignature)
+        ));
+        let strict = VerificationPolicy::new().validation_mode(ValidationMode::Strict);
+        assert!(public_key
+            .verify_with_policy(b"anything", &signature, false, &strict)
+            .is_err());
+        let zip215 = VerificationPolicy::new().validation_mode(ValidationMode::Zip215);
+        public_key
+            .verify_with_policy(b"anything", &signature, false, &zip215)
+            .expect("Signature didn't verify");
+        assert!(
+            public_key.verify_batch_with_policy(&[(b"anything", &signature)], false, &zip215)[0]
+                .is_ok()
+        );
+        let mut stream_verifier = public_key
+            .verify_stream_with_policy(&signature, &zip215)
+            .expect("Unable to create stream verifier");
+        stream_verifier.update(b"anything");
+        stream_verifier.finalize().expect("Signature didn't verify");
+    }
+
+    #[test]
+    fn resume_stream() {
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==",
+        )
+        .expect("Unable to decode the signature");
+
+        let mut stream_verifier = public_key
+            .verify_stream(&signature)
+            .expect("Can't extract StreamerVerifier");
+        stream_verifier.update(b"t");
+        stream_verifier.update(b"e");
+        let state = stream_verifier.export_state().expect("Can't export state");
+
+        let mut stream_verifier = public_key
+            .resume_stream(&signature, &state)
+            .expect("Can't resume StreamVerifier");
+        assert_eq!(stream_verifier.bytes_processed(), 2);
+        stream_verifier.update(b"st");
+        stream_verifier
+            .finalize()
+            .expect("Resumed signature didn't verify");
+
+        for i in [0, 4, 13, 20, 21, state.len() - 1] {
+            let mut corrupted = state.clone();
+            corrupted[i] ^= 1;
+            match public_key.resume_stream(&signature, &corrupted) {
+                Err(Error::InvalidStreamState) => {}
+                _ => panic!("Corrupted stream state accepted"),
+            }
+        }
+        match public_key.resume_stream(&signature, &state[1..]) {
+            Err(Error::InvalidStreamState) => {}
+            _ => panic!("Truncated stream state accepted"),
+        }
+
+        let mut other_signature = signature.clone();
+        other_signature.signature[0] ^= 1;
+        match public_key.resume_stream(&other_signature, &state) {
+            Err(Error::InvalidStreamState) => {}
+            _ => panic!("Stream state accepted for a different signature"),
+        }
+
+        // A counter close to 2^128, with a valid checksum
+        let mut crafted = state[..STREAM_STATE_BYTES - STREAM_STATE_CHECKSUMBYTES].to_vec();
+        for b in &mut crafted[21 + 64..21 + 80] {
+            *b = 0xff;
+        }
+        crafted[21 + 64] = 0x80;
+        let checksum = stream_state_checksum(&crafted, &signature);
+        crafted.extend_from_slice(&checksum);
+        match public_key.resume_stream(&signature, &crafted) {
+            Err(Error::InvalidEncoding) => {}
+            _ => panic!("Stream state with an overflowing counter accepted"),
+        }
+    }
+
+    #[test]
+    fn verify_stream_legacy() {
+        let public_key =
+            PublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+                .expect("Unable to decode the public key");
+        let signature = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==",
+        )
+        .expect("Unable to decode the signature");
+        match public_key.verify_stream(&signature) {
+            Err(Error::UnsupportedLegacyMode) => {}
+            _ => panic!("Legacy signature accepted without opting in"),
+        };
+
+        let policy = VerificationPolicy::new().allow_legacy_streaming(true);
+        for (chunks, valid) in [
+            (&[&b"te"[..], &b"st"[..]], true),
+            (&[&b"Te"[..], &b"st"[..]], false),
+        ] {
+            let mut stream_verifier = public_key
+                .verify_stream_with_policy(&signature, &policy)
+                .expect("Can't extract StreamerVerifier");
+            for chunk in chunks {
+                stream_verifier.update(chunk);
+            }
+            assert_eq!(stream_verifier.finalize().is_ok(), valid);
+        }
+    }
+
     #[test]
     fn verify_stream() {
         let public_key =
@@ -0,0 +1,222 @@
+use alloc::string::String;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Error, Signature, ValidationMode};
+
+#[derive(Clone, Copy, Debug)]
+enum Clock {
+    System,
+    Fixed(u64),
+    Custom(fn() -> u64),
+}
+
+impl Clock {
+    fn now(&self) -> Option<u64> {
+        match self {
+            #[cfg(feature = "std")]
+            Clock::System => Some(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+            ),
+            #[cfg(not(feature = "std"))]
+            Clock::System => None,
+            Clock::Fixed(now) => Some(*now),
+            Clock::Custom(f) => Some(f()),
+        }
+    }
+}
+
+/// How the `file:` field of the trusted comment is compared to the name of
+/// the artifact being verified
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileNameMatch {
+    /// Only compare the last path components, ignoring directories.
+    /// Minisign writes the base name of the file it signed, so this is
+    /// usually what should be used.
+    Basename,
+    /// Require the `file:` field to be identical to the expected name
+    Exact,
+}
+
+fn basename(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Additional checks to run on a signature once it has been verified
+///
+/// The default policy doesn't perform any checks, so that
+/// `PublicKey::verify_with_policy()` with `VerificationPolicy::default()`
+/// behaves exactly like `PublicKey::verify()`.
+///
+/// Checks only apply to the trusted comment, and are only run after the
+/// signature and the global signature have been verified, so that they never
+/// act on unauthenticated data.
+///
+/// Without the `std` feature, there is no system clock: timestamp checks
+/// require a clock to be set with `clock()` or `current_time()`, and fail
+/// with `Error::ClockUnavailable` otherwise.
+///
+/// ```rust
+/// use minisign_verify::VerificationPolicy;
+/// use std::time::Duration;
+///
+/// // Reject signatures older than 30 days, allowing clocks to be 5 minutes apart
+/// let policy = VerificationPolicy::new()
+///     .max_age(Duration::from_secs(30 * 86400))
+///     .max_clock_skew(Duration::from_secs(300));
+/// ```
+///
+/// A policy can also bind the signature to the name of the artifact, so that
+/// a validly signed but different file can't be substituted:
+///
+/// ```rust
+/// use minisign_verify::{FileNameMatch, VerificationPolicy};
+///
+/// let policy = VerificationPolicy::new()
+///     .expected_file_name("downloads/release-1.0.tar.gz", FileNameMatch::Basename);
+/// ```
+#[derive(Clone, Debug)]
+pub struct VerificationPolicy {
+    max_age: Option<Duration>,
+    max_clock_skew: Option<Duration>,
+    clock: Clock,
+    expected_file_name: Option<(String, FileNameMatch)>,
+    allow_legacy_streaming: bool,
+    validation_mode: ValidationMode,
+}
+
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VerificationPolicy {
+    /// Create a policy that doesn't perform any additional checks
+    pub const fn new() -> Self {
+        VerificationPolicy {
+            max_age: None,
+            max_clock_skew: None,
+            clock: Clock::System,
+            expected_file_name: None,
+            allow_legacy_streaming: false,
+            validation_mode: ValidationMode::Classic,
+        }
+    }
+
+    /// Reject signatures whose trusted timestamp is older than `max_age`
+    ///
+    /// Signatures from the future are also rejected. If a clock skew is set,
+    /// it extends the maximum age, and is tolerated for future timestamps;
+    /// otherwise, no skew is tolerated.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Reject signatures whose trusted timestamp is ahead of the current
+    /// time by more than `max_clock_skew`
+    pub fn max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.max_clock_skew = Some(max_clock_skew);
+        self
+    }
+
+    /// Use `clock` to get the current time, as a number of seconds since the
+    /// Unix epoch, instead of the system clock
+    pub fn clock(mut self, clock: fn() -> u64) -> Self {
+        self.clock = Clock::Custom(clock);
+        self
+    }
+
+    /// Use a fixed current time, as a number of seconds since the Unix epoch,
+    /// instead of the system clock
+    pub fn current_time(mut self, now: u64) -> Self {
+        self.clock = Clock::Fixed(now);
+        self
+    }
+
+    /// Require the `file:` field of the trusted comment to match `file_name`
+    pub fn expected_file_name<S: Into<String>>(
+        mut self,
+        file_name: S,
+        mode: FileNameMatch,
+    ) -> Self {
+        self.expected_file_name = Some((file_name.into(), mode));
+        self
+    }
+
+    /// Allow stream verifiers to verify legacy (non pre-hashed) signatures
+    ///
+    /// Legacy signatures are disabled by default, and should only be enabled
+    /// in order to support signatures made by older versions of Minisign.
+    /// This only affects stream verifiers: `PublicKey::verify_with_policy()`
+    /// has its own `allow_legacy` parameter.
+    pub fn allow_legacy_streaming(mut self, allow: bool) -> Self {
+        self.allow_legacy_streaming = allow;
+        self
+    }
+
+    pub(crate) fn allows_legacy_streaming(&self) -> bool {
+        self.allow_legacy_streaming
+    }
+
+    /// Use the Ed25519 validation rules of `mode`, instead of
+    /// `ValidationMode::Classic`
+    ///
+    /// This should be set when other implementations must accept and
+    /// reject exactly the same signatures as this one.
+    pub fn validation_mode(mut self, mode: ValidationMode) -> Self {
+        self.validation_mode = mode;
+        self
+    }
+
+    pub(crate) fn mode(&self) -> ValidationMode {
+        self.validation_mode
+    }
+
+    fn has_timestamp_checks(&self) -> bool {
+        self.max_age.is_some() || self.max_clock_skew.is_some()
+    }
+
+    /// Check a signature whose global signature has already been verified
+    pub(crate) fn check(&self, signature: &Signature) -> Result<(), Error> {
+        if !self.has_timestamp_checks() && self.expected_file_name.is_none() {
+            return Ok(());
+        }
+        let trusted_comment = signature.parse_trusted_comment()?;
+        if let Some((expected, mode)) = &self.expected_file_name {
+            let file = trusted_comment
+                .file()
+                .ok_or(Error::InvalidTrustedComment("file"))?;
+            let matches = match mode {
+                FileNameMatch::Basename => basename(file) == basename(expected),
+                FileNameMatch::Exact => file == expected,
+            };
+            if !matches {
+                return Err(Error::UnexpectedFileName);
+            }
+        }
+        if !self.has_timestamp_checks() {
+            return Ok(());
+        }
+        let timestamp = trusted_comment
+            .timestamp()
+            .ok_or(Error::InvalidTrustedComment("timestamp"))?;
+        let now = self.clock.now().ok_or(Error::ClockUnavailable)?;
+        let skew = self.max_clock_skew.map(|d| d.as_secs()).unwrap_or(0);
+        if timestamp > now.saturating_add(skew) {
+            return Err(Error::SignatureFromFuture);
+        }
+        if let Some(max_age) = self.max_age {
+            let oldest = now.saturating_sub(max_age.as_secs().saturating_add(skew));
+            if timestamp < oldest {
+                return Err(Error::SignatureExpired);
+            }
+        }
+        Ok(())
+    }
+}
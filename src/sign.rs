@@ -0,0 +1,338 @@
+#[cfg(feature = "sign")]
+use alloc::format;
+#[cfg(feature = "sign")]
+use alloc::string::ToString;
+#[cfg(feature = "sign")]
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::base64::{Base64, Decoder};
+use crate::crypto::blake2b::Blake2b;
+use crate::crypto::cryptoutil::wipe;
+use crate::crypto::{ed25519, scrypt};
+#[cfg(feature = "sign")]
+use crate::{prehash, Signature};
+use crate::{Error, PublicKey};
+
+#[cfg(feature = "sign")]
+const DEFAULT_SIGNATURE_UNTRUSTED_COMMENT: &str =
+    "untrusted comment: signature from minisign secret key";
+
+/// A Minisign secret key
+///
+/// A secret key can be decoded and checked against its public key with the
+/// `secret-key` feature. Creating signatures that `PublicKey::verify()`
+/// accepts requires the `sign` feature, which enables `secret-key`.
+#[derive(Clone)]
+pub struct SecretKey {
+    signature_algorithm: [u8; 2],
+    key_id: [u8; 8],
+    key: [u8; 64],
+}
+
+/// A Minisign key pair
+///
+/// This type is only available with the `sign` feature.
+#[cfg(feature = "sign")]
+#[derive(Clone, Debug)]
+pub struct KeyPair {
+    /// The public key, to be distributed to verifiers
+    pub public_key: PublicKey,
+    /// The secret key, to be kept private
+    pub secret_key: SecretKey,
+}
+
+#[cfg(feature = "sign")]
+impl KeyPair {
+    /// Derive a key pair from caller-supplied entropy
+    ///
+    /// The first 32 bytes of `entropy` are used as the Ed25519 seed, and the
+    /// last 8 bytes as the key ID. They must come from a cryptographically
+    /// secure random number generator: this crate doesn't have any.
+    pub fn from_entropy(entropy: &[u8; 40]) -> Self {
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&entropy[0..32]);
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&entropy[32..40]);
+        let secret_key = SecretKey::from_seed(&seed, key_id);
+        wipe(&mut seed);
+        KeyPair {
+            public_key: secret_key.public_key(),
+            secret_key,
+        }
+    }
+}
+
+impl SecretKey {
+    /// Create a Minisign secret key from a string, as in the `minisign.key`
+    /// file
+    ///
+    /// If the key is encrypted, `password` is used to decrypt it. It is
+    /// ignored for unencrypted keys.
+    ///
+    /// Returns `Error::InvalidPassword` if the key is encrypted and the
+    /// password is not the right one, and `Error::CorruptedSecretKey` if the
+    /// key is damaged.
+    pub fn decode(lines_str: &str, password: &str) -> Result<Self, Error> {
+        let mut lines = lines_str.lines();
+        lines.next().ok_or(Error::InvalidEncoding)?;
+        let mut bin = Base64::decode_to_vec(lines.next().ok_or(Error::InvalidEncoding)?)?;
+        let result = SecretKey::decode_bin(&mut bin, password);
+        wipe(&mut bin);
+        result
+    }
+
+    fn decode_bin(bin: &mut [u8], password: &str) -> Result<Self, Error> {
+        if bin.len() != 158 {
+            return Err(Error::InvalidEncoding);
+        }
+        let (header, keynum_sk) = bin.split_at_mut(54);
+        if &header[0..2] != b"Ed" || &header[4..6] != b"B2" {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let is_encrypted = match &header[2..4] {
+            b"Sc" => true,
+            b"\0\0" => false,
+            _ => return Err(Error::UnsupportedAlgorithm),
+        };
+        if is_encrypted {
+            let mut opslimit = [0u8; 8];
+            opslimit.copy_from_slice(&header[38..46]);
+            let mut memlimit = [0u8; 8];
+            memlimit.copy_from_slice(&header[46..54]);
+            let (n_log2, r, p) =
+                scrypt::pick_params(u64::from_le_bytes(opslimit), u64::from_le_bytes(memlimit));
+            let mut stream = [0u8; 104];
+            scrypt::scrypt(
+                password.as_bytes(),
+                &header[6..38],
+                n_log2,
+                r,
+                p,
+                &mut stream,
+            )
+            .ok_or(Error::CorruptedSecretKey)?;
+            for (k, s) in keynum_sk.iter_mut().zip(stream.iter()) {
+                *k ^= s;
+            }
+            wipe(&mut stream);
+        }
+        let mut chk = [0u8; 32];
+        let mut hasher = Blake2b::new(chk.len());
+        hasher.update(&header[0..2]);
+        hasher.update(&keynum_sk[0..72]);
+        hasher.finalize(&mut chk);
+        if chk
+            .iter()
+            .zip(keynum_sk[72..104].iter())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            != 0
+        {
+            return Err(if is_encrypted {
+                Error::InvalidPassword
+            } else {
+                Error::CorruptedSecretKey
+            });
+        }
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&keynum_sk[0..8]);
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&keynum_sk[8..40]);
+        let secret_key = SecretKey::from_seed(&seed, key_id);
+        wipe(&mut seed);
+        if secret_key.key[32..64] != keynum_sk[40..72] {
+            return Err(Error::CorruptedSecretKey);
+        }
+        Ok(secret_key)
+    }
+
+    /// Load a Minisign secret key from a file (such as the `minisign.key`
+    /// file)
+    ///
+    /// See `SecretKey::decode()` for how `password` is used.
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<Path>>(path: P, password: &str) -> Result<Self, Error> {
+        let bin = fs::read_to_string(path)?;
+        SecretKey::decode(&bin, password)
+    }
+
+    /// Create a secret key from a 32-byte Ed25519 seed and a key ID
+    pub fn from_seed(seed: &[u8; 32], key_id: [u8; 8]) -> Self {
+        let (key, _) = ed25519::keypair(seed);
+        SecretKey {
+            signature_algorithm: *b"Ed",
+            key_id,
+            key,
+        }
+    }
+
+    /// Return the key ID
+    pub fn key_id(&self) -> &[u8; 8] {
+        &self.key_id
+    }
+
+    /// Return the public key matching this secret key
+    pub fn public_key(&self) -> PublicKey {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&self.key[32..64]);
+        PublicKey {
+            untrusted_comment: None,
+            signature_algorithm: self.signature_algorithm,
+            key_id: self.key_id,
+            key,
+        }
+    }
+
+    /// Sign `bin`, with the given trusted comment
+    ///
+    /// If `prehashed` is `true`, the BLAKE2b-512 hash of `bin` is signed, as
+    /// done by default by Minisign. Otherwise, a legacy signature of the
+    /// content itself is created.
+    ///
+    /// The trusted comment is typically of the form
+    /// `timestamp:<seconds since the Unix epoch>\tfile:<file name>`, and
+    /// must fit on a single line.
+    #[cfg(feature = "sign")]
+    pub fn sign(
+        &self,
+        bin: &[u8],
+        trusted_comment: &str,
+        prehashed: bool,
+    ) -> Result<Signature, Error> {
+        if trusted_comment.contains(['\n', '\r']) {
+            return Err(Error::InvalidEncoding);
+        }
+        let signature = if prehashed {
+            ed25519::signature(&prehash(bin), &self.key)
+        } else {
+            ed25519::signature(bin, &self.key)
+        };
+        let mut global = Vec::with_capacity(signature.len() + trusted_comment.len());
+        global.extend_from_slice(&signature);
+        global.extend_from_slice(trusted_comment.as_bytes());
+        let global_signature = ed25519::signature(&global, &self.key);
+        Ok(Signature {
+            untrusted_comment: DEFAULT_SIGNATURE_UNTRUSTED_COMMENT.to_string(),
+            key_id: self.key_id,
+            signature,
+            trusted_comment: format!("trusted comment: {}", trusted_comment),
+            global_signature,
+            is_prehashed: prehashed,
+        })
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        wipe(&mut self.key);
+    }
+}
+
+#[cfg(feature = "sign")]
+#[test]
+fn test_sign() {
+    let keypair = KeyPair::from_entropy(&[1u8; 40]);
+    assert_eq!(
+        keypair.public_key.to_base64(),
+        "RWQBAQEBAQEBAYqI4910CfGV/VLbLTy6XXLKZwm/HZQSG/N0iAG0D29c"
+    );
+    let bin = b"release";
+    let trusted_comment = "timestamp:1700000000\tfile:release\thashed";
+    let signature = keypair
+        .secret_key
+        .sign(bin, trusted_comment, true)
+        .expect("Unable to sign");
+    assert_eq!(
+        signature.encode(),
+        "untrusted comment: signature from minisign secret key
+RUQBAQEBAQEBAU5h/WtOasu11/xhHix8x/oD+xrOqfPEuY5X9QvtMo9rm5aQwMqUQIxKlku47dvyJciH2pOPs7zLF2LpHKlnVgg=
+trusted comment: timestamp:1700000000\tfile:release\thashed
+BDaHSNoy1YBjuTr3cNYNwWlSdfjA8tiddg32rJLlOf10qW7D4EwZAJGGfoypTzWU0pasuN2hKmxlQ+4odDgeCg==
+"
+    );
+    keypair
+        .public_key
+        .verify(bin, &signature, false)
+        .expect("Signature didn't verify");
+
+    let signature = keypair
+        .secret_key
+        .sign(bin, trusted_comment, false)
+        .expect("Unable to sign");
+    keypair
+        .public_key
+        .verify(bin, &signature, true)
+        .expect("Signature didn't verify");
+    assert!(keypair
+        .public_key
+        .verify(b"Release", &signature, true)
+        .is_err());
+
+    assert!(keypair.secret_key.sign(bin, "a\nb", true).is_err());
+}
+
+#[test]
+fn test_secret_key_decode() {
+    use crate::base64::Encoder;
+
+    let encrypted = "untrusted comment: minisign encrypted secret key
+RWRTY0IyAAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8AgAAAAAAAAAAAAEAAAAAAHqA1iVWAi59PkTbRQEnWmvkP5s3JlGUYmFajpoJSLoG8OpZs2CSExeX/fpcu/xXSawjvE6KtYxgFczx404Uk1W9pxjMY1Sx3/7NkSyQQHSquGdk1ZmBNQoe4GOw4cyYKa0hayLQkQK4=
+";
+    let secret_key =
+        SecretKey::decode(encrypted, "correct horse").expect("Unable to decode the secret key");
+    assert_eq!(secret_key.key_id(), &[1u8; 8]);
+    assert_eq!(
+        secret_key.public_key().to_base64(),
+        "RWQBAQEBAQEBAYqI4910CfGV/VLbLTy6XXLKZwm/HZQSG/N0iAG0D29c"
+    );
+    match SecretKey::decode(encrypted, "battery staple") {
+        Err(Error::InvalidPassword) => {}
+        _ => panic!("Secret key decrypted with the wrong password"),
+    }
+
+    // Small memlimit, huge opslimit: a p parameter of about 2^27
+    let mut bin = Base64::decode_to_vec(encrypted.lines().nth(1).unwrap()).unwrap();
+    bin[38..46].copy_from_slice(&(1u64 << 40).to_le_bytes());
+    bin[46..54].copy_from_slice(&(1u64 << 14).to_le_bytes());
+    let hostile = format!(
+        "untrusted comment: minisign encrypted secret key\n{}\n",
+        Base64::encode_to_string(&bin).unwrap()
+    );
+    match SecretKey::decode(&hostile, "correct horse") {
+        Err(Error::CorruptedSecretKey) => {}
+        _ => panic!("Secret key with excessive scrypt parameters decoded"),
+    }
+
+    let unencrypted = "untrusted comment: minisign encrypted secret key
+RWQAAEIyAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAoE5dw6ofRdfVqNUZsNMfszLjYqRtO43ol32D1uPybOUHywMhZnxX/KGpIYykK9io6utzEO5F8IYxCCIBbeWvO8=
+";
+    let secret_key = SecretKey::decode(unencrypted, "").expect("Unable to decode the secret key");
+    assert_eq!(
+        secret_key.public_key().to_base64(),
+        "RWQCAgICAgICAoE5dw6ofRdfVqNUZsNMfszLjYqRtO43ol32D1uPybOU"
+    );
+    let corrupted = unencrypted.replace("oE5dw6", "oE5dw7");
+    match SecretKey::decode(&corrupted, "") {
+        Err(Error::CorruptedSecretKey) => {}
+        _ => panic!("Corrupted secret key decoded"),
+    }
+    match SecretKey::decode(
+        "untrusted comment: minisign encrypted secret key\nRWQAAEIy\n",
+        "",
+    ) {
+        Err(Error::InvalidEncoding) => {}
+        _ => panic!("Truncated secret key decoded"),
+    }
+}
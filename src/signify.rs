@@ -0,0 +1,604 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::base64::{Base64, Decoder};
+use crate::crypto::ed25519;
+use crate::crypto::sha512::Hash512_256;
+use crate::{Error, ValidationMode};
+
+const UNTRUSTED_COMMENT_PREFIX: &str = "untrusted comment: ";
+const SIGNIFY_ALGORITHM: &[u8; 2] = b"Ed";
+
+const GZIP_MAGIC: &[u8; 3] = &[0x1f, 0x8b, 0x08];
+const GZIP_FLAG_HCRC: u8 = 0x02;
+const GZIP_FLAG_EXTRA: u8 = 0x04;
+const GZIP_FLAG_NAME: u8 = 0x08;
+const GZIP_FLAG_COMMENT: u8 = 0x10;
+
+/// An OpenBSD signify public key
+///
+/// Signify keys use the same Ed25519 algorithm as Minisign keys, and are
+/// identified by an 8-byte key number.
+///
+/// ```rust
+/// use minisign_verify::{SignifyPublicKey, SignifySignature};
+///
+/// let public_key = SignifyPublicKey::decode(
+///     "untrusted comment: signify public key
+/// RWTjsdSixZ9rBwOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4",
+/// )
+/// .expect("Unable to decode the public key");
+///
+/// let signature = SignifySignature::decode(
+///     b"untrusted comment: verify with test.pub
+/// RWTjsdSixZ9rBzaHzuJ5Ol42cCsxEuklDYvLkK8MWaYwXBVXFjGsNPAy2FMVEZPrvl2Ex69fikBuIK7oQs9UAsyfA02FFtLjywc=
+/// ",
+/// )
+/// .expect("Unable to decode the signature");
+///
+/// public_key
+///     .verify(b"test\n", &signature)
+///     .expect("Signature didn't verify");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignifyPublicKey {
+    untrusted_comment: Option<String>,
+    key_number: [u8; 8],
+    key: [u8; 32],
+}
+
+/// The way a signify signature is stored
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SignifyFormat {
+    /// A detached signature of a file (`signify -S`)
+    Detached,
+    /// A signature followed by the signed message (`signify -S -e`)
+    Embedded,
+    /// A signature stored in the header of a gzip archive (`signify -S -z`)
+    Gzip(SignifyGzipHeader),
+}
+
+/// The content of the gzip header written by `signify -S -z`
+///
+/// The signature covers the hashes of the blocks of compressed data that
+/// follow the header, along with the other fields. None of them are
+/// authenticated before the archive has been verified.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignifyGzipHeader {
+    date: Option<String>,
+    key: Option<String>,
+    block_size: usize,
+    block_hashes: Vec<[u8; 32]>,
+    data_offset: usize,
+}
+
+/// An OpenBSD signify signature
+///
+/// Unlike Minisign signatures, signify signatures don't have a trusted
+/// comment: the untrusted comment is the only metadata, and the Ed25519
+/// signature is computed over the message itself.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignifySignature {
+    untrusted_comment: String,
+    key_number: [u8; 8],
+    signature: [u8; 64],
+    format: SignifyFormat,
+    message: Vec<u8>,
+}
+
+/// Split an untrusted comment and a base64 line from `data`
+///
+/// Returns the comment, the decoded base64 line, and the bytes that follow.
+fn split_lines(data: &[u8]) -> Result<(String, Vec<u8>, &[u8]), Error> {
+    let mut parts = data.splitn(3, |&c| c == b'\n');
+    let untrusted_comment = parts.next().ok_or(Error::InvalidEncoding)?;
+    let untrusted_comment =
+        str::from_utf8(untrusted_comment).map_err(|_| Error::InvalidEncoding)?;
+    if !untrusted_comment.starts_with(UNTRUSTED_COMMENT_PREFIX) {
+        return Err(Error::InvalidEncoding);
+    }
+    let bin = Base64::decode_to_vec(parts.next().ok_or(Error::InvalidEncoding)?)?;
+    if bin.len() < 2 || &bin[0..2] != SIGNIFY_ALGORITHM {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    Ok((
+        untrusted_comment.to_string(),
+        bin,
+        parts.next().unwrap_or(&[]),
+    ))
+}
+
+/// Return the position after the next zero byte of `data`, from `pos`
+fn skip_zero_terminated(data: &[u8], pos: usize) -> Result<usize, Error> {
+    let len = data
+        .get(pos..)
+        .and_then(|rest| rest.iter().position(|&c| c == 0))
+        .ok_or(Error::InvalidEncoding)?;
+    Ok(pos + len + 1)
+}
+
+fn decode_hex_digest(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (d, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let hi = (pair[0] as char).to_digit(16)?;
+        let lo = (pair[1] as char).to_digit(16)?;
+        *d = (hi << 4 | lo) as u8;
+    }
+    Some(digest)
+}
+
+impl SignifyPublicKey {
+    /// Create a signify public key from a base64 string
+    pub fn from_base64(public_key_b64: &str) -> Result<Self, Error> {
+        let bin = Base64::decode_to_vec(public_key_b64)?;
+        if bin.len() != 42 {
+            return Err(Error::InvalidEncoding);
+        }
+        if &bin[0..2] != SIGNIFY_ALGORITHM {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let mut key_number = [0u8; 8];
+        key_number.copy_from_slice(&bin[2..10]);
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&bin[10..42]);
+        Ok(SignifyPublicKey {
+            untrusted_comment: None,
+            key_number,
+            key,
+        })
+    }
+
+    /// Create a signify public key from a string, as in the `.pub` file
+    pub fn decode(lines_str: &str) -> Result<Self, Error> {
+        let (untrusted_comment, _, _) = split_lines(lines_str.as_bytes())?;
+        let public_key_b64 = lines_str.lines().nth(1).ok_or(Error::InvalidEncoding)?;
+        let mut public_key = SignifyPublicKey::from_base64(public_key_b64)?;
+        public_key.untrusted_comment = Some(untrusted_comment);
+        Ok(public_key)
+    }
+
+    /// Load a signify public key from a `.pub` file
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let bin = fs::read_to_string(path)?;
+        SignifyPublicKey::decode(&bin)
+    }
+
+    /// Return the untrusted comment, if there is one
+    pub fn untrusted_comment(&self) -> Option<&str> {
+        self.untrusted_comment.as_deref()
+    }
+
+    /// Return the key number
+    pub fn key_number(&self) -> &[u8; 8] {
+        &self.key_number
+    }
+
+    fn verify_message(&self, message: &[u8], signature: &SignifySignature) -> Result<(), Error> {
+        if self.key_number != signature.key_number {
+            return Err(Error::UnexpectedKeyId);
+        }
+        let mut verifier = ed25519::Verifier::new(&self.key, &signature.signature);
+        verifier.update(message);
+        if !verifier.finalize(ValidationMode::default()) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Verify that `signature` is a valid signature for `bin`
+    ///
+    /// Signatures stored in a gzip header don't sign the content directly,
+    /// and are rejected with `Error::UnexpectedAlgorithm`: use
+    /// `verify_gzip()` instead.
+    pub fn verify(&self, bin: &[u8], signature: &SignifySignature) -> Result<(), Error> {
+        if let SignifyFormat::Gzip(_) = signature.format {
+            return Err(Error::UnexpectedAlgorithm);
+        }
+        self.verify_message(bin, signature)
+    }
+
+    /// Verify a signature with an embedded message, and return the message
+    ///
+    /// Other signatures are rejected with `Error::UnexpectedAlgorithm`.
+    pub fn verify_embedded<'a>(&self, signature: &'a SignifySignature) -> Result<&'a [u8], Error> {
+        if signature.format != SignifyFormat::Embedded {
+            return Err(Error::UnexpectedAlgorithm);
+        }
+        self.verify_message(&signature.message, signature)?;
+        Ok(&signature.message)
+    }
+
+    /// Verify a gzip archive signed with `signify -S -z`
+    ///
+    /// `signature` must have been decoded from the header of `archive`. The
+    /// signature of the header is verified first, then the compressed data
+    /// is checked against the signed block hashes. Other signatures are
+    /// rejected with `Error::UnexpectedAlgorithm`.
+    pub fn verify_gzip(&self, archive: &[u8], signature: &SignifySignature) -> Result<(), Error> {
+        let header = match &signature.format {
+            SignifyFormat::Gzip(header) => header,
+            _ => return Err(Error::UnexpectedAlgorithm),
+        };
+        self.verify_message(&signature.message, signature)?;
+        let data = archive
+            .get(header.data_offset..)
+            .ok_or(Error::InvalidSignature)?;
+        let blocks = data.chunks(header.block_size);
+        if blocks.len() != header.block_hashes.len() {
+            return Err(Error::InvalidSignature);
+        }
+        for (block, expected) in blocks.zip(&header.block_hashes) {
+            let mut hasher = Hash512_256::new();
+            hasher.update(block);
+            if hasher.finalize() != *expected {
+                return Err(Error::InvalidSignature);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SignifyGzipHeader {
+    /// Parse the signed part of the comment of a gzip header: `key=value`
+    /// lines, an empty line, then one hexadecimal hash per block
+    fn parse(message: &[u8], data_offset: usize) -> Result<Self, Error> {
+        let message = str::from_utf8(message).map_err(|_| Error::InvalidEncoding)?;
+        let end = message.find("\n\n").ok_or(Error::InvalidEncoding)?;
+        let (mut date, mut key, mut algorithm, mut block_size) = (None, None, None, None);
+        for line in message[..end].split('\n') {
+            let (name, value) = line.split_once('=').ok_or(Error::InvalidEncoding)?;
+            match name {
+                "date" => date = Some(value.to_string()),
+                "key" => key = Some(value.to_string()),
+                "algorithm" => algorithm = Some(value),
+                "blocksize" => block_size = value.parse::<usize>().ok(),
+                _ => {}
+            }
+        }
+        if algorithm != Some("SHA512/256") {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let block_size = block_size
+            .filter(|&block_size| block_size > 0)
+            .ok_or(Error::InvalidEncoding)?;
+        let hashes = &message[end + 2..];
+        if !hashes.is_empty() && !hashes.ends_with('\n') {
+            return Err(Error::InvalidEncoding);
+        }
+        let block_hashes = hashes
+            .split_terminator('\n')
+            .map(decode_hex_digest)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::InvalidEncoding)?;
+        Ok(SignifyGzipHeader {
+            date,
+            key,
+            block_size,
+            block_hashes,
+            data_offset,
+        })
+    }
+
+    /// Return the signing date, as written by signify
+    pub fn date(&self) -> Option<&str> {
+        self.date.as_deref()
+    }
+
+    /// Return the name of the secret key file used for signing
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Return the size of the hashed blocks
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Return the SHA-512/256 hashes of the blocks of compressed data
+    pub fn block_hashes(&self) -> &[[u8; 32]] {
+        &self.block_hashes
+    }
+
+    /// Return the length of the gzip header, after which the compressed data
+    /// starts
+    pub fn data_offset(&self) -> usize {
+        self.data_offset
+    }
+}
+
+impl SignifySignature {
+    /// Create a signify signature from the content of a `.sig` file, a
+    /// signature with an embedded message, or a signed gzip archive
+    ///
+    /// The format is detected automatically. For gzip archives, only the
+    /// header is parsed.
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        if data.starts_with(GZIP_MAGIC) {
+            return SignifySignature::decode_gzip(data);
+        }
+        let (mut signature, message) = Self::decode_lines(data)?;
+        // Extra line endings after a detached signature are not a message
+        if message.iter().any(|&c| c != b'\n' && c != b'\r') {
+            signature.format = SignifyFormat::Embedded;
+            signature.message = message.to_vec();
+        }
+        Ok(signature)
+    }
+
+    /// Load a signify signature from a file
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let bin = fs::read(path)?;
+        SignifySignature::decode(&bin)
+    }
+
+    /// Decode the untrusted comment and the signature, as a detached
+    /// signature, and return it with the bytes that follow
+    fn decode_lines(data: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let (untrusted_comment, bin, message) = split_lines(data)?;
+        if bin.len() != 74 {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut key_number = [0u8; 8];
+        key_number.copy_from_slice(&bin[2..10]);
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(&bin[10..74]);
+        let signature = SignifySignature {
+            untrusted_comment,
+            key_number,
+            signature,
+            format: SignifyFormat::Detached,
+            message: Vec::new(),
+        };
+        Ok((signature, message))
+    }
+
+    fn decode_gzip(data: &[u8]) -> Result<Self, Error> {
+        // RFC 1952 header, with the signature in the comment field
+        if data.len() < 10 {
+            return Err(Error::InvalidEncoding);
+        }
+        let flags = data[3];
+        if flags & GZIP_FLAG_COMMENT == 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut pos = 10;
+        if flags & GZIP_FLAG_EXTRA != 0 {
+            let len = data.get(pos..pos + 2).ok_or(Error::InvalidEncoding)?;
+            pos += 2 + (len[0] as usize | (len[1] as usize) << 8);
+        }
+        if flags & GZIP_FLAG_NAME != 0 {
+            pos = skip_zero_terminated(data, pos)?;
+        }
+        let comment_start = pos;
+        pos = skip_zero_terminated(data, pos)?;
+        let comment = &data[comment_start..pos - 1];
+        if flags & GZIP_FLAG_HCRC != 0 {
+            pos += 2;
+        }
+        if pos > data.len() {
+            return Err(Error::InvalidEncoding);
+        }
+        let (mut signature, message) = Self::decode_lines(comment)?;
+        signature.format = SignifyFormat::Gzip(SignifyGzipHeader::parse(message, pos)?);
+        signature.message = message.to_vec();
+        Ok(signature)
+    }
+
+    /// Return the untrusted comment of the signature
+    pub fn untrusted_comment(&self) -> &str {
+        &self.untrusted_comment
+    }
+
+    /// Return the number of the key that created the signature
+    pub fn key_number(&self) -> &[u8; 8] {
+        &self.key_number
+    }
+
+    /// Return the way the signature is stored, along with the parsed gzip
+    /// header for signed archives
+    pub fn format(&self) -> &SignifyFormat {
+        &self.format
+    }
+}
+
+#[cfg(test)]
+const TEST_PUBLIC_KEY: &str = "untrusted comment: signify public key
+RWTjsdSixZ9rBwOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4
+";
+
+/// A gzip archive of "signify gzip test\n", signed with a block size of 16
+#[cfg(test)]
+fn test_archive() -> Vec<u8> {
+    let mut archive = vec![0x1f, 0x8b, 0x08, 0x10, 0, 0, 0, 0, 0, 0x03];
+    archive.extend_from_slice(
+        b"untrusted comment: verify with test.pub
+RWTjsdSixZ9rB99vsbdNM1+akbwyAiap6p9ZkAxL62y921k7is51pybfH5rNQl3cO7E6uRXJMVc5FHybxTiBatpNIYPLYyxAxwY=
+date=2026-10-16T12:00:00Z
+key=test.sec
+algorithm=SHA512/256
+blocksize=16
+
+72995c2fae6729ef50608d51e2b3c3c5ffb8648f7af5076f3beabf3e2fc6d79c
+47b2e18a7db1132d2b03e539d98fb1c5c759d64fbd99ccf8ee4848432cbc9618
+\0",
+    );
+    archive.extend_from_slice(&[
+        0x2b, 0xce, 0x4c, 0xcf, 0xcb, 0x4c, 0xab, 0x54, 0x48, 0xaf, 0xca, 0x2c, 0x50, 0x28, 0x49,
+        0x2d, 0x2e, 0xe1, 0x02, 0x00, 0x9c, 0xc6, 0xfc, 0x01, 0x12, 0x00, 0x00, 0x00,
+    ]);
+    archive
+}
+
+#[test]
+fn test_signify_detached() {
+    let public_key = SignifyPublicKey::decode(TEST_PUBLIC_KEY).unwrap();
+    assert_eq!(
+        public_key.untrusted_comment(),
+        Some("untrusted comment: signify public key")
+    );
+    assert_eq!(
+        public_key.key_number(),
+        &[0xe3, 0xb1, 0xd4, 0xa2, 0xc5, 0x9f, 0x6b, 0x07]
+    );
+    let signature = SignifySignature::decode(
+        b"untrusted comment: verify with test.pub
+RWTjsdSixZ9rBzaHzuJ5Ol42cCsxEuklDYvLkK8MWaYwXBVXFjGsNPAy2FMVEZPrvl2Ex69fikBuIK7oQs9UAsyfA02FFtLjywc=
+",
+    )
+    .unwrap();
+    assert_eq!(
+        signature.untrusted_comment(),
+        "untrusted comment: verify with test.pub"
+    );
+    assert_eq!(signature.key_number(), public_key.key_number());
+    assert_eq!(signature.format(), &SignifyFormat::Detached);
+    public_key.verify(b"test\n", &signature).unwrap();
+    assert!(matches!(
+        public_key.verify(b"test", &signature),
+        Err(Error::InvalidSignature)
+    ));
+    assert!(matches!(
+        public_key.verify_embedded(&signature),
+        Err(Error::UnexpectedAlgorithm)
+    ));
+
+    // A different key number
+    let mut other_key = public_key.clone();
+    other_key.key_number[0] ^= 1;
+    assert!(matches!(
+        other_key.verify(b"test\n", &signature),
+        Err(Error::UnexpectedKeyId)
+    ));
+
+    // Minisign public keys have the same layout, but pre-hashed Minisign
+    // signatures use a different algorithm
+    assert!(SignifyPublicKey::decode(
+        "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"
+    )
+    .is_ok());
+    assert!(matches!(
+        SignifySignature::decode(
+            b"untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+"
+        ),
+        Err(Error::UnsupportedAlgorithm)
+    ));
+    assert!(matches!(
+        SignifySignature::decode(b"signify signature\nRWTjsdSixZ9rBw==\n"),
+        Err(Error::InvalidEncoding)
+    ));
+}
+
+#[test]
+fn test_signify_trailing_line_endings() {
+    let public_key = SignifyPublicKey::decode(TEST_PUBLIC_KEY).unwrap();
+    for trailer in [&b"\n"[..], b"\r\n", b"\n\n\r\n"].iter() {
+        let mut data = b"untrusted comment: verify with test.pub
+RWTjsdSixZ9rBzaHzuJ5Ol42cCsxEuklDYvLkK8MWaYwXBVXFjGsNPAy2FMVEZPrvl2Ex69fikBuIK7oQs9UAsyfA02FFtLjywc=
+"
+        .to_vec();
+        data.extend_from_slice(trailer);
+        let signature = SignifySignature::decode(&data).unwrap();
+        assert_eq!(signature.format(), &SignifyFormat::Detached);
+        assert!(signature.message.is_empty());
+        public_key.verify(b"test\n", &signature).unwrap();
+    }
+}
+
+#[test]
+fn test_signify_embedded() {
+    let public_key = SignifyPublicKey::decode(TEST_PUBLIC_KEY).unwrap();
+    let mut data = b"untrusted comment: verify with test.pub
+RWTjsdSixZ9rB7d+OBd+mrleWey2E2WtWGV/X5JqneKZEpM7vameKd4PaCmbK5Ua3GxMtvAwLHenqWtPUEMSDoM2sNfPpVXagw0=
+embedded message
+"
+    .to_vec();
+    let signature = SignifySignature::decode(&data).unwrap();
+    assert_eq!(signature.format(), &SignifyFormat::Embedded);
+    assert_eq!(
+        public_key.verify_embedded(&signature).unwrap(),
+        b"embedded message\n"
+    );
+    public_key
+        .verify(b"embedded message\n", &signature)
+        .unwrap();
+
+    let len = data.len();
+    data[len - 2] ^= 1;
+    let signature = SignifySignature::decode(&data).unwrap();
+    assert!(matches!(
+        public_key.verify_embedded(&signature),
+        Err(Error::InvalidSignature)
+    ));
+}
+
+#[test]
+fn test_signify_gzip() {
+    let public_key = SignifyPublicKey::decode(TEST_PUBLIC_KEY).unwrap();
+    let archive = test_archive();
+    let signature = SignifySignature::decode(&archive).unwrap();
+    let header = match signature.format() {
+        SignifyFormat::Gzip(header) => header,
+        format => panic!("Unexpected format: {:?}", format),
+    };
+    assert_eq!(header.date(), Some("2026-10-16T12:00:00Z"));
+    assert_eq!(header.key(), Some("test.sec"));
+    assert_eq!(header.block_size(), 16);
+    assert_eq!(header.block_hashes().len(), 2);
+    assert_eq!(header.data_offset(), archive.len() - 28);
+    public_key.verify_gzip(&archive, &signature).unwrap();
+    assert!(matches!(
+        public_key.verify(&archive, &signature),
+        Err(Error::UnexpectedAlgorithm)
+    ));
+
+    // Corrupted, truncated and extended compressed data
+    let mut corrupted = archive.clone();
+    let len = corrupted.len();
+    corrupted[len - 20] ^= 1;
+    assert!(matches!(
+        public_key.verify_gzip(&corrupted, &signature),
+        Err(Error::InvalidSignature)
+    ));
+    assert!(matches!(
+        public_key.verify_gzip(&archive[..len - 16], &signature),
+        Err(Error::InvalidSignature)
+    ));
+    let mut extended = archive.clone();
+    extended.extend_from_slice(&[0u8; 16]);
+    assert!(matches!(
+        public_key.verify_gzip(&extended, &signature),
+        Err(Error::InvalidSignature)
+    ));
+
+    // A modified header field
+    let mut tampered = archive.clone();
+    let pos = archive
+        .windows(12)
+        .position(|w| w == b"key=test.sec")
+        .unwrap();
+    tampered[pos + 4] = b'b';
+    let signature = SignifySignature::decode(&tampered).unwrap();
+    assert!(matches!(
+        public_key.verify_gzip(&tampered, &signature),
+        Err(Error::InvalidSignature)
+    ));
+
+    // An archive without a signature
+    assert!(matches!(
+        SignifySignature::decode(&[0x1f, 0x8b, 0x08, 0, 0, 0, 0, 0, 0, 0x03]),
+        Err(Error::InvalidEncoding)
+    ));
+}
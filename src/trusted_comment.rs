@@ -0,0 +1,208 @@
+use core::str::Split;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+
+/// A parsed trusted comment
+///
+/// Minisign writes the trusted comment as a list of tab-separated `key:value`
+/// fields, for example `timestamp:1633700835\tfile:test\thashed`.
+///
+/// The well-known fields are exposed as typed values, and everything else can
+/// be enumerated with [`TrustedComment::unknown_fields()`]. Comments that
+/// don't follow this layout (custom comments set with `minisign -t`) are still
+/// accepted, and simply show up as unknown fields. Empty fields, such as
+/// the ones left by doubled or trailing tabs, are ignored.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrustedComment<'a> {
+    raw: &'a str,
+    timestamp: Option<u64>,
+    file: Option<&'a str>,
+    is_prehashed: bool,
+}
+
+/// An iterator over the fields of a trusted comment that are not
+/// recognized by [`TrustedComment`]
+///
+/// Each item is a `(key, value)` pair. Fields without a `:` separator are
+/// returned with an empty value.
+#[derive(Clone, Debug)]
+pub struct UnknownFields<'a> {
+    fields: Split<'a, char>,
+}
+
+fn split_field(field: &str) -> (&str, Option<&str>) {
+    match field.find(':') {
+        Some(pos) => (&field[..pos], Some(&field[pos + 1..])),
+        None => (field, None),
+    }
+}
+
+fn is_known_key(key: &str) -> bool {
+    matches!(key, "timestamp" | "file" | "hashed" | "prehashed")
+}
+
+impl<'a> TrustedComment<'a> {
+    /// Parse a trusted comment, without its `trusted comment: ` prefix
+    ///
+    /// Returns `Error::InvalidTrustedComment` if a well-known field is
+    /// present but malformed, or is present more than once.
+    pub fn parse(raw: &'a str) -> Result<Self, Error> {
+        let mut timestamp = None;
+        let mut file = None;
+        let mut is_prehashed = false;
+        for field in raw.split('\t') {
+            if field.is_empty() {
+                continue;
+            }
+            match split_field(field) {
+                ("timestamp", Some(value)) => {
+                    if timestamp.is_some()
+                        || value.is_empty()
+                        || !value.bytes().all(|c| c.is_ascii_digit())
+                    {
+                        return Err(Error::InvalidTrustedComment("timestamp"));
+                    }
+                    timestamp = Some(
+                        value
+                            .parse()
+                            .map_err(|_| Error::InvalidTrustedComment("timestamp"))?,
+                    );
+                }
+                ("file", Some(value)) => {
+                    if file.is_some() {
+                        return Err(Error::InvalidTrustedComment("file"));
+                    }
+                    file = Some(value);
+                }
+                ("hashed", None) | ("prehashed", None) => {
+                    if is_prehashed {
+                        return Err(Error::InvalidTrustedComment("hashed"));
+                    }
+                    is_prehashed = true;
+                }
+                ("timestamp", None) => return Err(Error::InvalidTrustedComment("timestamp")),
+                ("file", None) => return Err(Error::InvalidTrustedComment("file")),
+                ("hashed", Some(_)) | ("prehashed", Some(_)) => {
+                    return Err(Error::InvalidTrustedComment("hashed"))
+                }
+                _ => {}
+            }
+        }
+        Ok(TrustedComment {
+            raw,
+            timestamp,
+            file,
+            is_prehashed,
+        })
+    }
+
+    /// Return the raw trusted comment
+    pub fn as_str(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Return the `timestamp:` field, as a number of seconds since the Unix
+    /// epoch
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
+    /// Return the `timestamp:` field as a `SystemTime`
+    #[cfg(feature = "std")]
+    pub fn system_time(&self) -> Option<SystemTime> {
+        self.timestamp
+            .and_then(|ts| UNIX_EPOCH.checked_add(Duration::from_secs(ts)))
+    }
+
+    /// Return the `file:` field, the base name of the signed file
+    pub fn file(&self) -> Option<&'a str> {
+        self.file
+    }
+
+    /// Return `true` if the comment carries the `hashed` (or `prehashed`)
+    /// marker written by Minisign for pre-hashed signatures
+    pub fn is_prehashed(&self) -> bool {
+        self.is_prehashed
+    }
+
+    /// Return an iterator over the fields that are not recognized
+    pub fn unknown_fields(&self) -> UnknownFields<'a> {
+        UnknownFields {
+            fields: self.raw.split('\t'),
+        }
+    }
+}
+
+impl<'a> Iterator for UnknownFields<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for field in &mut self.fields {
+            if field.is_empty() {
+                continue;
+            }
+            let (key, value) = split_field(field);
+            if !is_known_key(key) {
+                return Some((key, value.unwrap_or("")));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn test_trusted_comment_fields() {
+    let tc = TrustedComment::parse("timestamp:1633700835\tfile:test\tprehashed\tsize:4\tbeta")
+        .expect("Unable to parse the trusted comment");
+    assert_eq!(tc.timestamp(), Some(1633700835));
+    #[cfg(feature = "std")]
+    assert_eq!(
+        tc.system_time(),
+        Some(UNIX_EPOCH + Duration::from_secs(1633700835))
+    );
+    assert_eq!(tc.file(), Some("test"));
+    assert!(tc.is_prehashed());
+    let unknown: Vec<_> = tc.unknown_fields().collect();
+    assert_eq!(unknown, vec![("size", "4"), ("beta", "")]);
+
+    let tc = TrustedComment::parse("Release 1.0").expect("Unable to parse the trusted comment");
+    assert_eq!(tc.timestamp(), None);
+    assert_eq!(tc.file(), None);
+    assert!(!tc.is_prehashed());
+    assert_eq!(tc.unknown_fields().count(), 1);
+}
+
+#[test]
+fn test_trusted_comment_malformed() {
+    for raw in [
+        "timestamp:abc\tfile:test",
+        "timestamp:\tfile:test",
+        "timestamp:+1\tfile:test",
+        "timestamp:99999999999999999999999",
+        "timestamp:1\ttimestamp:2",
+    ] {
+        match TrustedComment::parse(raw) {
+            Err(Error::InvalidTrustedComment("timestamp")) => {}
+            _ => panic!("Malformed timestamp accepted: {:?}", raw),
+        }
+    }
+    match TrustedComment::parse("timestamp:1\tfile:a\tfile:b") {
+        Err(Error::InvalidTrustedComment("file")) => {}
+        _ => panic!("Duplicate file field accepted"),
+    }
+}
+
+#[test]
+fn test_trusted_comment_empty_fields() {
+    let tc = TrustedComment::parse("").expect("Unable to parse the trusted comment");
+    assert_eq!(tc.timestamp(), None);
+    assert_eq!(tc.unknown_fields().count(), 0);
+
+    let tc = TrustedComment::parse("timestamp:1\t\tfile:a\t")
+        .expect("Unable to parse the trusted comment");
+    assert_eq!(tc.timestamp(), Some(1));
+    assert_eq!(tc.file(), Some("a"));
+    assert_eq!(tc.unknown_fields().count(), 0);
+}
@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+
+const PREHASHED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==
+";
+
+const LEGACY_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==
+";
+
+/// A temporary directory with a signed file, its signature at the default
+/// location, and the public key at the default location
+struct Fixture {
+    dir: PathBuf,
+}
+
+impl Fixture {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "minisign-verify-cli-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("test"), b"test").unwrap();
+        fs::write(dir.join("test.minisig"), PREHASHED_SIGNATURE).unwrap();
+        fs::write(dir.join("legacy.sig"), LEGACY_SIGNATURE).unwrap();
+        fs::write(
+            dir.join("minisign.pub"),
+            format!("untrusted comment: minisign public key\n{}\n", PUBLIC_KEY),
+        )
+        .unwrap();
+        Fixture { dir }
+    }
+
+    fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_minisign-verify"))
+            .current_dir(&self.dir)
+            .args(args)
+            .output()
+            .expect("Unable to run minisign-verify")
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn verify() {
+    let fixture = Fixture::new("verify");
+
+    // Default signature and public key files
+    let output = fixture.run(&["-Vm", "test"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "Signature and comment signature verified\n\
+         Trusted comment: timestamp:1633700835\tfile:test\tprehashed\n"
+    );
+
+    let output = fixture.run(&["-V", "-P", PUBLIC_KEY, "-m", "test", "-x", "test.minisig"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+
+    // Legacy signatures are accepted, like minisign does
+    let output = fixture.run(&["-V", "-m", "test", "-xlegacy.sig", "-q"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    let output = fixture.run(&["-VQm", "test"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "timestamp:1633700835\tfile:test\tprehashed\n"
+    );
+}
+
+#[test]
+fn output() {
+    let fixture = Fixture::new("output");
+    let output = fixture.run(&["-Vom", "test"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(output.stdout, b"test");
+    assert!(stderr(&output).starts_with("Signature and comment signature verified\n"));
+
+    let output = fixture.run(&["-Vom", "test", "-q"]);
+    assert_eq!(output.stdout, b"test");
+    assert!(output.stderr.is_empty());
+
+    // Nothing is written if the signature doesn't verify
+    fs::write(fixture.path().join("test"), b"Test").unwrap();
+    let output = fixture.run(&["-Vom", "test"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn failures() {
+    let fixture = Fixture::new("failures");
+    fs::write(fixture.path().join("tampered"), b"Test").unwrap();
+    fs::copy(
+        fixture.path().join("test.minisig"),
+        fixture.path().join("tampered.minisig"),
+    )
+    .unwrap();
+    let output = fixture.run(&["-Vm", "tampered"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(stderr(&output), "Signature verification failed\n");
+
+    let output = fixture.run(&[
+        "-Vm",
+        "test",
+        "-P",
+        "RWQBAQEBAQEBAYqI4910CfGV/VLbLTy6XXLKZwm/HZQSG/N0iAG0D29c",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "Signature key id in test.minisig is E7620F1842B4E81F\n\
+         but the key id in the public key is 0101010101010101\n"
+    );
+
+    let output = fixture.run(&["-Vm", "missing"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("missing.minisig: "));
+
+    let output = fixture.run(&["-Vm", "test", "-p", "missing.pub"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("missing.pub: "));
+}
+
+#[test]
+fn usage() {
+    let fixture = Fixture::new("usage");
+    for args in [&["-V"][..], &["-Vm"], &["-Vm", "test", "extra"], &["-Vk"]] {
+        let output = fixture.run(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(stderr(&output).contains("Usage:"));
+    }
+    let output = fixture.run(&["-v"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("minisign-verify "));
+}
@@ -0,0 +1,324 @@
+//! WebAssembly exports for [minisign-verify](https://crates.io/crates/minisign-verify).
+//!
+//! This crate builds a `wasm32-unknown-unknown` module exporting plain
+//! functions, without any JavaScript bindings:
+//!
+//! ```bash
+//! cargo build -p minisign-verify-wasm --target wasm32-unknown-unknown --profile wasm
+//! ```
+//!
+//! Inputs are passed as `(pointer, length)` pairs into the module's linear
+//! memory, in buffers obtained with `minisign_alloc()` and released with
+//! `minisign_dealloc()`. Public keys, signatures and stream verifiers are
+//! returned as opaque handles, through a 4-byte output location, and must be
+//! released with the matching `*_free()` function.
+//!
+//! Fallible functions return `0` on success, or an error code. The codes are
+//! the same as the ones of the C bindings.
+//!
+//! The module doesn't import anything, and never accesses the file system or
+//! the clock.
+
+use std::{mem, ptr, slice, str};
+
+use minisign_verify::{Error, PublicKey, Signature, StreamVerifier, VerificationPolicy};
+
+/// Success
+pub const MINISIGN_OK: i32 = 0;
+/// The provided string couldn't be decoded properly
+pub const MINISIGN_ERR_INVALID_ENCODING: i32 = 1;
+/// A field of the trusted comment is malformed, duplicated, or missing
+pub const MINISIGN_ERR_INVALID_TRUSTED_COMMENT: i32 = 2;
+/// The signature verification failed
+pub const MINISIGN_ERR_INVALID_SIGNATURE: i32 = 3;
+/// Fewer distinct keys than required produced a valid signature
+pub const MINISIGN_ERR_INSUFFICIENT_SIGNATURES: i32 = 4;
+/// The password of an encrypted secret key is incorrect
+pub const MINISIGN_ERR_INVALID_PASSWORD: i32 = 5;
+/// The secret key is damaged
+pub const MINISIGN_ERR_CORRUPTED_SECRET_KEY: i32 = 6;
+/// No clock is available to check the timestamp
+pub const MINISIGN_ERR_CLOCK_UNAVAILABLE: i32 = 7;
+/// The signature has expired
+pub const MINISIGN_ERR_SIGNATURE_EXPIRED: i32 = 8;
+/// The signature timestamp is in the future
+pub const MINISIGN_ERR_SIGNATURE_FROM_FUTURE: i32 = 9;
+/// An I/O error occurred
+pub const MINISIGN_ERR_IO: i32 = 10;
+/// The algorithm doesn't match what was expected
+pub const MINISIGN_ERR_UNEXPECTED_ALGORITHM: i32 = 11;
+/// The file name in the trusted comment doesn't match the expected one
+pub const MINISIGN_ERR_UNEXPECTED_FILE_NAME: i32 = 12;
+/// The key ID from the signature doesn't match the public key
+pub const MINISIGN_ERR_UNEXPECTED_KEY_ID: i32 = 13;
+/// The input is larger than the configured limit
+pub const MINISIGN_ERR_INPUT_TOO_LARGE: i32 = 14;
+/// A saved stream verification state is invalid
+pub const MINISIGN_ERR_INVALID_STREAM_STATE: i32 = 15;
+/// The signature algorithm is not supported
+pub const MINISIGN_ERR_UNSUPPORTED_ALGORITHM: i32 = 16;
+/// Legacy signatures are not supported by stream verifiers
+pub const MINISIGN_ERR_UNSUPPORTED_LEGACY_MODE: i32 = 17;
+/// A required pointer is null, or a string is not valid UTF-8
+pub const MINISIGN_ERR_INVALID_ARGUMENT: i32 = 100;
+
+fn status(result: Result<(), Error>) -> i32 {
+    match result {
+        Ok(()) => MINISIGN_OK,
+        Err(e) => e.code(),
+    }
+}
+
+unsafe fn to_slice<'a>(data: *const u8, len: usize) -> Result<&'a [u8], i32> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(MINISIGN_ERR_INVALID_ARGUMENT);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn to_str<'a>(data: *const u8, len: usize) -> Result<&'a str, i32> {
+    str::from_utf8(to_slice(data, len)?).map_err(|_| MINISIGN_ERR_INVALID_ARGUMENT)
+}
+
+unsafe fn new_handle<T>(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut T,
+    decode: fn(&str) -> Result<T, Error>,
+) -> i32 {
+    if out.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    *out = ptr::null_mut();
+    let s = match to_str(data, len) {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    match decode(s) {
+        Ok(x) => {
+            *out = Box::into_raw(Box::new(x));
+            MINISIGN_OK
+        }
+        Err(e) => e.code(),
+    }
+}
+
+/// Allocate `len` bytes in linear memory, to pass inputs to the module
+///
+/// Returns a null pointer if `len` is `0` or if the allocation failed.
+#[no_mangle]
+pub extern "C" fn minisign_alloc(len: usize) -> *mut u8 {
+    if len == 0 {
+        return ptr::null_mut();
+    }
+    let mut buf = Vec::<u8>::new();
+    if buf.try_reserve_exact(len).is_err() {
+        return ptr::null_mut();
+    }
+    let p = buf.as_mut_ptr();
+    mem::forget(buf);
+    p
+}
+
+/// Release a buffer allocated with `minisign_alloc()`
+///
+/// # Safety
+///
+/// `p` must have been returned by `minisign_alloc(len)`, with the same `len`.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_dealloc(p: *mut u8, len: usize) {
+    if !p.is_null() {
+        drop(Vec::from_raw_parts(p, 0, len));
+    }
+}
+
+/// Decode a public key, either as a base64 string or as the content of a
+/// `minisign.pub` file
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, and `out` to a writable handle.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_public_key_decode(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut PublicKey,
+) -> i32 {
+    new_handle(data, len, out, |s| {
+        let s = s.trim();
+        if s.contains('\n') {
+            PublicKey::decode(s)
+        } else {
+            PublicKey::from_base64(s)
+        }
+    })
+}
+
+/// Release a public key
+///
+/// # Safety
+///
+/// `public_key` must be null or a handle that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_public_key_free(public_key: *mut PublicKey) {
+    if !public_key.is_null() {
+        drop(Box::from_raw(public_key));
+    }
+}
+
+/// Decode a signature, as stored in a `.sig` file
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, and `out` to a writable handle.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_signature_decode(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut Signature,
+) -> i32 {
+    new_handle(data, len, out, Signature::decode)
+}
+
+/// Return a pointer to the trusted comment of a signature, and store its
+/// length into `*len`
+///
+/// # Safety
+///
+/// `signature` must be a valid handle, and `len` must point to a writable
+/// location.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_signature_trusted_comment(
+    signature: *const Signature,
+    len: *mut usize,
+) -> *const u8 {
+    if signature.is_null() || len.is_null() {
+        return ptr::null();
+    }
+    let trusted_comment = (*signature).trusted_comment();
+    *len = trusted_comment.len();
+    trusted_comment.as_ptr()
+}
+
+/// Release a signature
+///
+/// # Safety
+///
+/// `signature` must be null or a handle that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_signature_free(signature: *mut Signature) {
+    if !signature.is_null() {
+        drop(Box::from_raw(signature));
+    }
+}
+
+/// Verify that `signature` is a valid signature for `data`
+///
+/// Legacy signatures are only accepted if `allow_legacy` is not `0`.
+///
+/// # Safety
+///
+/// `public_key` and `signature` must be valid handles, and `data` must point
+/// to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_verify(
+    public_key: *const PublicKey,
+    data: *const u8,
+    len: usize,
+    signature: *const Signature,
+    allow_legacy: i32,
+) -> i32 {
+    if public_key.is_null() || signature.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    match to_slice(data, len) {
+        Ok(data) => status((*public_key).verify(data, &*signature, allow_legacy != 0)),
+        Err(code) => code,
+    }
+}
+
+/// Create a stream verifier for a pre-hashed signature
+///
+/// The verifier keeps its own copies of the public key and the signature.
+///
+/// # Safety
+///
+/// `public_key` and `signature` must be valid handles, and `out` must point
+/// to a writable handle.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_stream_verifier_new(
+    public_key: *const PublicKey,
+    signature: *const Signature,
+    out: *mut *mut StreamVerifier<'static>,
+) -> i32 {
+    if public_key.is_null() || signature.is_null() || out.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    *out = ptr::null_mut();
+    match (*public_key)
+        .clone()
+        .into_stream_verifier((*signature).clone(), VerificationPolicy::new())
+    {
+        Ok(stream_verifier) => {
+            *out = Box::into_raw(Box::new(stream_verifier));
+            MINISIGN_OK
+        }
+        Err(e) => e.code(),
+    }
+}
+
+/// Update a stream verifier with a chunk of data
+///
+/// # Safety
+///
+/// `stream_verifier` must be a valid handle, and `data` must point to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_stream_verifier_update(
+    stream_verifier: *mut StreamVerifier<'static>,
+    data: *const u8,
+    len: usize,
+) -> i32 {
+    if stream_verifier.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    match to_slice(data, len) {
+        Ok(data) => {
+            (*stream_verifier).update(data);
+            MINISIGN_OK
+        }
+        Err(code) => code,
+    }
+}
+
+/// Verify the signature over the data processed so far
+///
+/// # Safety
+///
+/// `stream_verifier` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_stream_verifier_finalize(
+    stream_verifier: *mut StreamVerifier<'static>,
+) -> i32 {
+    if stream_verifier.is_null() {
+        return MINISIGN_ERR_INVALID_ARGUMENT;
+    }
+    status((*stream_verifier).finalize())
+}
+
+/// Release a stream verifier
+///
+/// # Safety
+///
+/// `stream_verifier` must be null or a handle that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn minisign_stream_verifier_free(
+    stream_verifier: *mut StreamVerifier<'static>,
+) {
+    if !stream_verifier.is_null() {
+        drop(Box::from_raw(stream_verifier));
+    }
+}
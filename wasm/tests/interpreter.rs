@@ -0,0 +1,206 @@
+//! Load the WebAssembly module in an interpreter, and drive its exports the
+//! way a host would
+
+use std::path::Path;
+use std::process::Command;
+
+use wasmi::{Engine, Instance, Linker, Memory, Module, Store, WasmParams, WasmResults};
+
+const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+
+const PREHASHED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==";
+
+const LEGACY_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==";
+
+/// Build the module with the size-optimized profile
+///
+/// Returns `None` if the `wasm32-unknown-unknown` target is not installed.
+fn build_module() -> Option<Vec<u8>> {
+    let sysroot = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .expect("Unable to run rustc");
+    let sysroot = String::from_utf8(sysroot.stdout).unwrap();
+    if !Path::new(sysroot.trim())
+        .join("lib/rustlib/wasm32-unknown-unknown")
+        .exists()
+    {
+        eprintln!("wasm32-unknown-unknown target not installed, skipping");
+        return None;
+    }
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let target_dir = manifest_dir.join("../target/wasm-test");
+    let status = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir)
+        .args([
+            "build",
+            "-p",
+            "minisign-verify-wasm",
+            "--target",
+            "wasm32-unknown-unknown",
+            "--profile",
+            "wasm",
+        ])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("Unable to run cargo");
+    assert!(status.success(), "Unable to build the WebAssembly module");
+    Some(
+        std::fs::read(target_dir.join("wasm32-unknown-unknown/wasm/minisign_verify_wasm.wasm"))
+            .unwrap(),
+    )
+}
+
+struct Host {
+    store: Store<()>,
+    instance: Instance,
+    memory: Memory,
+}
+
+impl Host {
+    fn new(wasm: &[u8]) -> Self {
+        let engine = Engine::default();
+        let module = Module::new(&engine, wasm).expect("Invalid module");
+        assert_eq!(module.imports().count(), 0);
+        let mut store = Store::new(&engine, ());
+        let instance = Linker::new(&engine)
+            .instantiate_and_start(&mut store, &module)
+            .expect("Unable to instantiate the module");
+        let memory = instance.get_memory(&store, "memory").expect("No memory");
+        Host {
+            store,
+            instance,
+            memory,
+        }
+    }
+
+    fn call<P: WasmParams, R: WasmResults>(&mut self, name: &str, params: P) -> R {
+        self.instance
+            .get_typed_func::<P, R>(&self.store, name)
+            .unwrap_or_else(|_| panic!("Missing export: {}", name))
+            .call(&mut self.store, params)
+            .unwrap_or_else(|e| panic!("{} trapped: {}", name, e))
+    }
+
+    fn write(&mut self, bin: &[u8]) -> (i32, i32) {
+        let p: i32 = self.call("minisign_alloc", bin.len() as i32);
+        self.memory.write(&mut self.store, p as usize, bin).unwrap();
+        (p, bin.len() as i32)
+    }
+
+    fn read(&self, p: i32, len: usize) -> Vec<u8> {
+        let mut bin = vec![0u8; len];
+        self.memory.read(&self.store, p as usize, &mut bin).unwrap();
+        bin
+    }
+
+    fn read_u32(&self, p: i32) -> i32 {
+        let bin = self.read(p, 4);
+        i32::from_le_bytes([bin[0], bin[1], bin[2], bin[3]])
+    }
+
+    fn decode(&mut self, function: &str, text: &str) -> Result<i32, i32> {
+        let (p, len) = self.write(text.as_bytes());
+        let (out, _) = self.write(&[0u8; 4]);
+        let code: i32 = self.call(function, (p, len, out));
+        let handle = self.read_u32(out);
+        self.call::<_, ()>("minisign_dealloc", (p, len));
+        self.call::<_, ()>("minisign_dealloc", (out, 4));
+        if code == 0 {
+            Ok(handle)
+        } else {
+            assert_eq!(handle, 0);
+            Err(code)
+        }
+    }
+}
+
+#[test]
+fn wasm_module() {
+    let wasm = match build_module() {
+        Some(wasm) => wasm,
+        None => return,
+    };
+    let mut host = Host::new(&wasm);
+
+    assert_eq!(host.call::<_, i32>("minisign_alloc", -1), 0);
+    assert_eq!(host.call::<_, i32>("minisign_alloc", 0), 0);
+
+    let public_key = host
+        .decode("minisign_public_key_decode", PUBLIC_KEY)
+        .expect("Unable to decode the public key");
+    let public_key_file = format!("untrusted comment: minisign public key\n{}\n", PUBLIC_KEY);
+    let other_public_key = host
+        .decode("minisign_public_key_decode", &public_key_file)
+        .expect("Unable to decode the public key file");
+    host.call::<_, ()>("minisign_public_key_free", other_public_key);
+    assert_eq!(
+        host.decode("minisign_public_key_decode", "not a key"),
+        Err(1)
+    );
+    let prehashed = host
+        .decode("minisign_signature_decode", PREHASHED_SIGNATURE)
+        .expect("Unable to decode the signature");
+    let legacy = host
+        .decode("minisign_signature_decode", LEGACY_SIGNATURE)
+        .expect("Unable to decode the signature");
+
+    let (len_out, _) = host.write(&[0u8; 4]);
+    let trusted_comment: i32 =
+        host.call("minisign_signature_trusted_comment", (prehashed, len_out));
+    let len = host.read_u32(len_out) as usize;
+    assert_eq!(
+        host.read(trusted_comment, len),
+        b"timestamp:1633700835\tfile:test\tprehashed"
+    );
+
+    // One-shot verification
+    let (data, data_len) = host.write(b"test");
+    let (bad_data, _) = host.write(b"Test");
+    let code: i32 = host.call(
+        "minisign_verify",
+        (public_key, data, data_len, prehashed, 0),
+    );
+    assert_eq!(code, 0);
+    let code: i32 = host.call(
+        "minisign_verify",
+        (public_key, bad_data, data_len, prehashed, 0),
+    );
+    assert_eq!(code, 3);
+    let code: i32 = host.call("minisign_verify", (public_key, data, data_len, legacy, 0));
+    assert_eq!(code, 11);
+    let code: i32 = host.call("minisign_verify", (public_key, data, data_len, legacy, 1));
+    assert_eq!(code, 0);
+
+    // Streaming verification
+    for (chunks, expected) in [([data, data + 2], 0), ([bad_data, data + 2], 3)] {
+        let (out, _) = host.write(&[0u8; 4]);
+        let code: i32 = host.call("minisign_stream_verifier_new", (public_key, prehashed, out));
+        assert_eq!(code, 0);
+        let stream_verifier = host.read_u32(out);
+        for chunk in chunks {
+            let code: i32 = host.call(
+                "minisign_stream_verifier_update",
+                (stream_verifier, chunk, 2),
+            );
+            assert_eq!(code, 0);
+        }
+        let code: i32 = host.call("minisign_stream_verifier_finalize", stream_verifier);
+        assert_eq!(code, expected);
+        host.call::<_, ()>("minisign_stream_verifier_free", stream_verifier);
+    }
+    let (out, _) = host.write(&[0u8; 4]);
+    let code: i32 = host.call("minisign_stream_verifier_new", (public_key, legacy, out));
+    assert_eq!(code, 17);
+
+    host.call::<_, ()>("minisign_signature_free", legacy);
+    host.call::<_, ()>("minisign_signature_free", prehashed);
+    host.call::<_, ()>("minisign_public_key_free", public_key);
+}